@@ -1,11 +1,20 @@
-use serde_yaml::Value;
+use serde_yaml::{Mapping, Value};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use super::Adapter;
+use super::{Adapter, repo_script};
+use crate::config::normalize_newlines;
+use crate::hooks::GIT_HOOKS;
+use log::debug;
 
 const HOOKS_DIR_NAMES: &[&str] = &[".hooks", "git-hooks"];
 
+/// Suffix of the optional metadata file next to a script.
+const SIDECAR_SUFFIX: &str = ".lhm.yml";
+
+/// Keys a sidecar may add to the generated command.
+const SIDECAR_KEYS: &[&str] = &["glob", "files", "exclude", "tags"];
+
 /// Adapter for conventional hooks directories in the repo root.
 ///
 /// Detects `.hooks/` or `git-hooks/` (first match wins) and generates lefthook
@@ -23,8 +32,48 @@ fn find_hooks_dir(root: &Path) -> Option<&'static str> {
     HOOKS_DIR_NAMES.iter().copied().find(|name| root.join(name).is_dir())
 }
 
+/// Returns `true` if the script hands off to lefthook or lhm itself. Wrapping
+/// such a script would re-enter lhm and loop.
+fn invokes_hook_manager(path: &Path) -> bool {
+    let Ok(content) = fs::read(path) else {
+        return false;
+    };
+    String::from_utf8_lossy(&content).lines().any(runs_hook_manager)
+}
+
+/// Words after which the next word is the command being run.
+const COMMAND_PREFIXES: &[&str] = &["npx", "exec", "&&", "||", ";", "|"];
+
+/// Whether a script line runs lefthook or lhm: as the line's first word, or
+/// after `npx`, `exec` or a command separator. Comments are ignored, so a
+/// script that only mentions them still runs.
+fn runs_hook_manager(line: &str) -> bool {
+    let mut command_start = true;
+    for word in line.split_whitespace() {
+        if word.starts_with('#') {
+            return false;
+        }
+        let program = word.trim_matches(|c| c == '"' || c == '\'');
+        let program = program.rsplit('/').next().unwrap_or(program);
+        if command_start && (program == "lefthook" || program == "lhm") {
+            return true;
+        }
+        command_start = COMMAND_PREFIXES.contains(&word);
+    }
+    false
+}
+
+/// Name endings of editor backups and merge leftovers, never run as hooks.
+const BACKUP_SUFFIXES: &[&str] = &["~", ".bak", ".swp", ".orig"];
+
+/// Whether `name` is a dotfile or a backup/temp file rather than a hook script.
+fn is_ignored_file(name: &str) -> bool {
+    name.starts_with('.') || BACKUP_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
 /// Collect sorted filenames from `hooks_dir` that match `hook_name` exactly
-/// or start with `{hook_name}-`.
+/// or start with `{hook_name}-`, skipping dotfiles, backup files and scripts
+/// that invoke lefthook or lhm.
 fn matching_scripts(hooks_dir: &Path, hook_name: &str) -> Vec<String> {
     let prefix = format!("{hook_name}-");
     let Ok(entries) = fs::read_dir(hooks_dir) else {
@@ -33,19 +82,106 @@ fn matching_scripts(hooks_dir: &Path, hook_name: &str) -> Vec<String> {
     let mut names: Vec<String> = entries
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_file())
+        .filter(|e| !e.file_name().to_string_lossy().ends_with(SIDECAR_SUFFIX))
+        .filter(|e| !is_ignored_file(&e.file_name().to_string_lossy()))
         .filter_map(|e| {
             let name = e.file_name().to_string_lossy().into_owned();
-            if name == hook_name || name.starts_with(&prefix) {
-                Some(name)
-            } else {
-                None
+            if name != hook_name && !name.starts_with(&prefix) {
+                return None;
+            }
+            if invokes_hook_manager(&e.path()) {
+                debug!("skipping {}: it runs lefthook or lhm itself", e.path().display());
+                return None;
             }
+            Some(name)
         })
         .collect();
     names.sort();
     names
 }
 
+/// Read `<script>.lhm.yml` next to a script and return the supported keys it
+/// sets (`glob`, `files`, `exclude`, `tags`). Missing or unparsable sidecars
+/// yield nothing.
+fn sidecar_settings(hooks_dir: &Path, script: &str) -> Mapping {
+    let path = hooks_dir.join(format!("{script}{SIDECAR_SUFFIX}"));
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Mapping::new();
+    };
+    let Ok(Value::Mapping(settings)) = serde_yaml::from_str(&normalize_newlines(&content)) else {
+        debug!("ignoring sidecar {}: not a YAML mapping", path.display());
+        return Mapping::new();
+    };
+    settings
+        .into_iter()
+        .filter(|(k, _)| {
+            let known = k.as_str().is_some_and(|k| SIDECAR_KEYS.contains(&k));
+            if !known {
+                debug!("ignoring unsupported key {k:?} in {}", path.display());
+            }
+            known
+        })
+        .collect()
+}
+
+/// The interpreter to run a script with, from its shebang line, so scripts
+/// without the execute bit (fresh clones, Windows checkouts) still run.
+/// `#!/usr/bin/env python3` gives `python3`, `#!/bin/bash -e` gives
+/// `/bin/bash -e`, and a file without a shebang runs with `sh` unless it is
+/// executable: then it may be a compiled binary and runs directly (`None`).
+fn interpreter(path: &Path) -> Option<String> {
+    let Ok(content) = fs::read(path) else {
+        return Some("sh".to_string());
+    };
+    let Some(shebang) = content.strip_prefix(b"#!") else {
+        return (!is_executable(path)).then(|| "sh".to_string());
+    };
+    let shebang = String::from_utf8_lossy(shebang);
+    let shebang = shebang.lines().next().unwrap_or_default();
+    let mut words: Vec<&str> = shebang.split_whitespace().collect();
+    if words
+        .first()
+        .is_some_and(|w| Path::new(w).file_name().is_some_and(|n| n == "env"))
+    {
+        words.remove(0);
+        if words.first() == Some(&"-S") {
+            words.remove(0);
+        }
+    }
+    if words.is_empty() {
+        return Some("sh".to_string());
+    }
+    Some(words.join(" "))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+/// Name the generated command `<dir>-<script>` (e.g. `hooks-pre-commit-detekt`
+/// for `.hooks/pre-commit-detekt`) so it is readable in merged output and
+/// unlikely to collide with hand-written global command names.
+fn command_name(dir_name: &str, script: &str) -> String {
+    format!("{}-{script}", dir_name.trim_start_matches('.'))
+}
+
+/// Positional placeholders forwarding git's arguments for `hook_name`, so the
+/// scripts see the argv git would have given them.
+fn hook_args(hook_name: &str) -> Option<&'static str> {
+    match hook_name {
+        "commit-msg" | "applypatch-msg" => Some("{1}"),
+        "prepare-commit-msg" => Some("{1} {2} {3}"),
+        "pre-push" => Some("{1} {2}"),
+        _ => None,
+    }
+}
+
+/// Whether `hook_name` edits a commit message, so its scripts may prompt.
+fn is_message_hook(hook_name: &str) -> bool {
+    matches!(hook_name, "commit-msg" | "prepare-commit-msg" | "applypatch-msg")
+}
+
 impl Adapter for HooksDirAdapter {
     fn name(&self) -> &str {
         "hooks-dir"
@@ -63,21 +199,58 @@ impl Adapter for HooksDirAdapter {
             return None;
         }
 
-        let commands: Vec<String> = scripts
+        let mut commands = Mapping::new();
+        for script in &scripts {
+            let path = repo_script(&format!("{dir_name}/{script}"));
+            let mut run = match interpreter(&hooks_dir.join(script)) {
+                Some(interpreter) => format!("{interpreter} {path}"),
+                None => path,
+            };
+            let mut command = sidecar_settings(&hooks_dir, script);
+            if let Some(args) = hook_args(hook_name) {
+                run = format!("{run} {args}");
+            }
+            // Message-editing hooks may prompt the user, so let lefthook attach a TTY.
+            if is_message_hook(hook_name) {
+                command.insert("interactive".into(), Value::Bool(true));
+            }
+            command.insert("run".into(), run.into());
+            commands.insert(command_name(dir_name, script).into(), Value::Mapping(command));
+        }
+
+        let mut hook = Mapping::new();
+        hook.insert("commands".into(), Value::Mapping(commands));
+        let mut config = Mapping::new();
+        config.insert(hook_name.into(), Value::Mapping(hook));
+        Some(Value::Mapping(config))
+    }
+
+    fn supported_hooks(&self, root: &Path) -> Vec<String> {
+        let Some(dir_name) = find_hooks_dir(root) else {
+            return Vec::new();
+        };
+        let hooks_dir = root.join(dir_name);
+        GIT_HOOKS
             .iter()
-            .map(|script| {
-                let cmd_name = if *script == hook_name {
-                    "hooks-dir".to_string()
-                } else {
-                    let suffix = &script[hook_name.len() + 1..];
-                    format!("hooks-dir-{suffix}")
-                };
-                format!("    {cmd_name}:\n      run: {dir_name}/{script}")
-            })
-            .collect();
-
-        let yaml = format!("{hook_name}:\n  commands:\n{}\n", commands.join("\n"));
-        serde_yaml::from_str(&yaml).ok()
+            .filter(|h| !matching_scripts(&hooks_dir, h).is_empty())
+            .map(|h| h.to_string())
+            .collect()
+    }
+
+    fn detection_markers(&self) -> &'static [&'static str] {
+        HOOKS_DIR_NAMES
+    }
+
+    /// Both candidate hooks dirs (creating `.hooks/` takes over from
+    /// `git-hooks/`) and every file in the one in use: scripts and sidecars.
+    fn config_sources(&self, root: &Path) -> Vec<PathBuf> {
+        let mut sources: Vec<PathBuf> = HOOKS_DIR_NAMES.iter().map(|name| root.join(name)).collect();
+        if let Some(entries) = find_hooks_dir(root).and_then(|name| fs::read_dir(root.join(name)).ok()) {
+            let mut files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect();
+            files.sort();
+            sources.extend(files);
+        }
+        sources
     }
 }
 
@@ -92,6 +265,25 @@ mod tests {
         HooksDirAdapter
     }
 
+    #[test]
+    fn test_config_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("git-hooks");
+        fs::create_dir_all(hooks_dir.join("lib")).unwrap();
+        fs::write(hooks_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks_dir.join("pre-commit.lhm.yml"), "glob: '*.rs'\n").unwrap();
+
+        assert_eq!(
+            adapter().config_sources(dir.path()),
+            [
+                dir.path().join(".hooks"),
+                hooks_dir.clone(),
+                hooks_dir.join("pre-commit"),
+                hooks_dir.join("pre-commit.lhm.yml"),
+            ]
+        );
+    }
+
     #[test]
     fn test_detect_with_dot_hooks() {
         let dir = tempfile::tempdir().unwrap();
@@ -214,9 +406,12 @@ mod tests {
 
         let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
         let out = serde_yaml::to_string(&config).unwrap();
-        assert!(out.contains("hooks-dir:"), "has exact match cmd: {out}");
-        assert!(out.contains("hooks-dir-checkstyle:"), "has checkstyle cmd: {out}");
-        assert!(out.contains("hooks-dir-detekt:"), "has detekt cmd: {out}");
+        assert!(out.contains("hooks-pre-commit:"), "has exact match cmd: {out}");
+        assert!(
+            out.contains("hooks-pre-commit-checkstyle:"),
+            "has checkstyle cmd: {out}"
+        );
+        assert!(out.contains("hooks-pre-commit-detekt:"), "has detekt cmd: {out}");
         assert!(
             out.contains(".hooks/pre-commit-checkstyle"),
             "has checkstyle run: {out}"
@@ -225,6 +420,34 @@ mod tests {
         assert!(!out.contains("pre-push"), "should not contain pre-push: {out}");
     }
 
+    #[test]
+    fn test_matching_scripts_ignores_backup_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        for name in [
+            "pre-commit",
+            "pre-commit-lint",
+            "pre-commit~",
+            "pre-commit-lint~",
+            "pre-commit.bak",
+            "pre-commit-lint.bak",
+            "pre-commit-lint.swp",
+            ".pre-commit.swp",
+            "pre-commit-lint.orig",
+            ".pre-commit-hidden",
+        ] {
+            fs::write(hooks_dir.join(name), "#!/bin/sh\n").unwrap();
+        }
+
+        assert_eq!(
+            matching_scripts(&hooks_dir, "pre-commit"),
+            ["pre-commit", "pre-commit-lint"]
+        );
+        assert!(is_ignored_file(".pre-commit.swp"));
+        assert!(!is_ignored_file("pre-commit-backup"));
+    }
+
     #[test]
     fn test_generate_config_prefixed_only_no_exact_match() {
         let dir = tempfile::tempdir().unwrap();
@@ -235,9 +458,12 @@ mod tests {
         let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
         let out = serde_yaml::to_string(&config).unwrap();
         assert!(out.contains("pre-commit:"), "has hook key: {out}");
-        assert!(out.contains("hooks-dir-ktlint:"), "has ktlint cmd: {out}");
+        assert!(out.contains("hooks-pre-commit-ktlint:"), "has ktlint cmd: {out}");
         assert!(out.contains(".hooks/pre-commit-ktlint"), "has ktlint run: {out}");
-        assert!(!out.contains("hooks-dir:\n"), "should not have exact match cmd: {out}");
+        assert!(
+            !out.contains("hooks-pre-commit:\n"),
+            "should not have exact match cmd: {out}"
+        );
     }
 
     #[test]
@@ -250,8 +476,8 @@ mod tests {
 
         let config = adapter().generate_config(dir.path(), "pre-push").unwrap();
         let out = serde_yaml::to_string(&config).unwrap();
-        assert!(out.contains("hooks-dir:"), "has exact match cmd: {out}");
-        assert!(out.contains("hooks-dir-detekt:"), "has detekt cmd: {out}");
+        assert!(out.contains("git-hooks-pre-push:"), "has exact match cmd: {out}");
+        assert!(out.contains("git-hooks-pre-push-detekt:"), "has detekt cmd: {out}");
         assert!(out.contains("git-hooks/pre-push"), "uses git-hooks path: {out}");
         assert!(
             out.contains("git-hooks/pre-push-detekt"),
@@ -278,6 +504,297 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_config_commit_msg_receives_message_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("commit-msg"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks_dir.join("commit-msg-lint"), "#!/bin/sh\n").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "commit-msg").unwrap();
+        let cmds = &config["commit-msg"]["commands"];
+        assert_eq!(
+            cmds["hooks-commit-msg"]["run"].as_str(),
+            Some(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/commit-msg" {1}"#)
+        );
+        assert_eq!(
+            cmds["hooks-commit-msg-lint"]["run"].as_str(),
+            Some(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/commit-msg-lint" {1}"#)
+        );
+        assert_eq!(cmds["hooks-commit-msg"]["interactive"].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_generate_config_forwards_git_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        for (hook, args, interactive) in [
+            ("prepare-commit-msg", "{1} {2} {3}", Some(true)),
+            ("applypatch-msg", "{1}", Some(true)),
+            ("pre-push", "{1} {2}", None),
+        ] {
+            fs::write(hooks_dir.join(hook), "#!/bin/sh\n").unwrap();
+            let config = adapter().generate_config(dir.path(), hook).unwrap();
+            let cmd = &config[hook]["commands"][format!("hooks-{hook}").as_str()];
+            assert_eq!(
+                cmd["run"].as_str(),
+                Some(format!(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/{hook}" {args}"#).as_str()),
+                "{hook}"
+            );
+            assert_eq!(cmd["interactive"].as_bool(), interactive, "{hook}");
+        }
+    }
+
+    #[test]
+    fn test_generate_config_no_args_for_other_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let cmd = &config["pre-commit"]["commands"]["hooks-pre-commit"];
+        assert_eq!(
+            cmd["run"].as_str(),
+            Some(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/pre-commit""#)
+        );
+        assert!(cmd.get("interactive").is_none());
+    }
+
+    #[test]
+    fn test_command_names_do_not_collide_with_global_hooks_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+
+        let global: Value =
+            serde_yaml::from_str("pre-commit:\n  commands:\n    hooks-dir:\n      run: global-hooks-dir\n").unwrap();
+        let generated = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let merged = crate::merge::merge_configs(global, generated);
+
+        let cmds = &merged["pre-commit"]["commands"];
+        assert_eq!(cmds["hooks-dir"]["run"].as_str(), Some("global-hooks-dir"));
+        assert_eq!(
+            cmds["hooks-pre-commit"]["run"].as_str(),
+            Some(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/pre-commit""#)
+        );
+    }
+
+    #[test]
+    fn test_interpreter_from_shebang() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases = [
+            ("#!/usr/bin/env python3\nprint('hi')\n", "python3"),
+            ("#!/bin/bash\nset -e\n", "/bin/bash"),
+            ("#! /bin/bash -eu\n", "/bin/bash -eu"),
+            ("#!/usr/bin/env -S deno run --allow-read\n", "deno run --allow-read"),
+            ("make lint\n", "sh"),
+            ("#!\n", "sh"),
+            ("", "sh"),
+        ];
+        for (content, expected) in cases {
+            let script = dir.path().join("script");
+            fs::write(&script, content).unwrap();
+            assert_eq!(interpreter(&script).as_deref(), Some(expected), "{content:?}");
+        }
+        assert_eq!(interpreter(&dir.path().join("missing")).as_deref(), Some("sh"));
+    }
+
+    #[test]
+    fn test_interpreter_executable_without_shebang_runs_directly() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("pre-commit-lint");
+        fs::write(&binary, b"\x7fELF\x02\x01\x01\0\0\0\xff\xfe").unwrap();
+        // without the execute bit it can only be a script
+        assert_eq!(interpreter(&binary).as_deref(), Some("sh"));
+
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(interpreter(&binary), None);
+
+        let script = dir.path().join("pre-commit-py");
+        fs::write(&script, "#!/usr/bin/env python3\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(interpreter(&script).as_deref(), Some("python3"));
+    }
+
+    #[test]
+    fn test_generate_config_runs_compiled_hook_directly() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        let binary = hooks_dir.join("pre-commit");
+        fs::write(&binary, b"\x7fELF\x02\x01\x01\0").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        assert_eq!(
+            config["pre-commit"]["commands"]["hooks-pre-commit"]["run"].as_str(),
+            Some(r#""$(git rev-parse --show-toplevel)/.hooks/pre-commit""#)
+        );
+    }
+
+    #[test]
+    fn test_generate_config_interpreter_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("pre-commit-py"), "#!/usr/bin/env python3\n").unwrap();
+        fs::write(hooks_dir.join("pre-commit-bash"), "#!/usr/bin/env bash\n").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let cmds = &config["pre-commit"]["commands"];
+        assert_eq!(
+            cmds["hooks-pre-commit-py"]["run"].as_str(),
+            Some(r#"python3 "$(git rev-parse --show-toplevel)/.hooks/pre-commit-py""#)
+        );
+        assert_eq!(
+            cmds["hooks-pre-commit-bash"]["run"].as_str(),
+            Some(r#"bash "$(git rev-parse --show-toplevel)/.hooks/pre-commit-bash""#)
+        );
+    }
+
+    #[test]
+    fn test_sidecar_settings_added_to_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("pre-commit-eslint"), "#!/bin/sh\n").unwrap();
+        fs::write(
+            hooks_dir.join("pre-commit-eslint.lhm.yml"),
+            "glob: \"*.{js,ts}\"\ntags: [frontend]\nrun: ignored\n",
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("pre-commit-fmt"), "#!/bin/sh\n").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let cmds = config["pre-commit"]["commands"].as_mapping().unwrap();
+        assert_eq!(cmds.len(), 2, "sidecar is not a script: {cmds:?}");
+        let eslint = &cmds["hooks-pre-commit-eslint"];
+        assert_eq!(eslint["glob"].as_str(), Some("*.{js,ts}"));
+        assert_eq!(eslint["tags"], serde_yaml::from_str::<Value>("[frontend]").unwrap());
+        assert_eq!(
+            eslint["run"].as_str(),
+            Some(r#"/bin/sh "$(git rev-parse --show-toplevel)/.hooks/pre-commit-eslint""#)
+        );
+        assert!(cmds["hooks-pre-commit-fmt"].get("glob").is_none());
+    }
+
+    #[test]
+    fn test_supported_hooks_from_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("pre-push-detekt"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks_dir.join("commit-msg"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks_dir.join("helper.sh"), "#!/bin/sh\n").unwrap();
+
+        assert_eq!(adapter().supported_hooks(dir.path()), ["commit-msg", "pre-push"]);
+    }
+
+    #[test]
+    fn test_skips_scripts_that_run_lefthook() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(
+            hooks_dir.join("pre-commit"),
+            "#!/bin/sh\nlefthook run pre-commit \"$@\"\n",
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("pre-commit-lhm"), "#!/bin/sh\nexec lhm dry-run\n").unwrap();
+        fs::write(hooks_dir.join("pre-commit-lint"), "#!/bin/sh\nexec lint-lhmish\n").unwrap();
+
+        assert_eq!(matching_scripts(&hooks_dir, "pre-commit"), ["pre-commit-lint"]);
+    }
+
+    #[test]
+    fn test_runs_hook_manager_only_in_command_position() {
+        assert!(runs_hook_manager("lefthook run pre-commit"));
+        assert!(runs_hook_manager("  exec lhm run pre-commit \"$@\""));
+        assert!(runs_hook_manager("npx lefthook run pre-commit"));
+        assert!(runs_hook_manager(
+            "set -e && ./node_modules/.bin/lefthook run pre-commit"
+        ));
+        assert!(!runs_hook_manager("# ported from lefthook"));
+        assert!(!runs_hook_manager("make lint # see lhm docs"));
+        assert!(!runs_hook_manager("echo lefthook is not used here"));
+    }
+
+    #[test]
+    fn test_runs_scripts_that_mention_lefthook_in_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(
+            hooks_dir.join("pre-commit"),
+            "#!/bin/sh\n# ported from lefthook, see lhm docs\nmake lint\n",
+        )
+        .unwrap();
+
+        assert_eq!(matching_scripts(&hooks_dir, "pre-commit"), ["pre-commit"]);
+    }
+
+    #[test]
+    fn test_git_hooks_running_lefthook_are_never_wrapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join(".git/hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("pre-commit"), "#!/bin/sh\nlefthook run pre-commit\n").unwrap();
+
+        assert!(!adapter().detect(dir.path()));
+        assert!(adapter().generate_config(dir.path(), "pre-commit").is_none());
+    }
+
+    /// The shape of the script `lefthook install` writes to `.git/hooks`.
+    const NATIVE_LEFTHOOK_SCRIPT: &str = r#"#!/bin/sh
+
+if [ "$LEFTHOOK_VERBOSE" = "1" -o "$LEFTHOOK_VERBOSE" = "true" ]; then
+  set -x
+fi
+
+if [ "$LEFTHOOK" = "0" ]; then
+  exit 0
+fi
+
+call_lefthook()
+{
+  if lefthook -h >/dev/null 2>&1
+  then
+    lefthook "$@"
+  fi
+}
+
+call_lefthook run "pre-commit" "$@"
+"#;
+
+    #[test]
+    fn test_native_lefthook_install_not_wrapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let git_hooks = dir.path().join(".git/hooks");
+        let hooks_dir = dir.path().join(".hooks");
+        fs::create_dir_all(&git_hooks).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(git_hooks.join("pre-commit"), NATIVE_LEFTHOOK_SCRIPT).unwrap();
+        fs::write(hooks_dir.join("pre-commit-lint"), "#!/bin/sh\nmake lint\n").unwrap();
+
+        // Only the repo's own hooks dir is wrapped, never lefthook's script in .git/hooks
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let commands = config["pre-commit"]["commands"].as_mapping().unwrap();
+        assert_eq!(commands.len(), 1);
+        assert!(commands.contains_key("hooks-pre-commit-lint"));
+
+        // A copy of lefthook's script in the hooks dir is skipped too
+        fs::write(hooks_dir.join("pre-commit"), NATIVE_LEFTHOOK_SCRIPT).unwrap();
+        assert_eq!(matching_scripts(&hooks_dir, "pre-commit"), ["pre-commit-lint"]);
+    }
+
     #[test]
     fn test_matching_scripts_sorted() {
         let dir = tempfile::tempdir().unwrap();
@@ -1,12 +1,17 @@
-use serde_yaml::Value;
-use std::path::Path;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use super::Adapter;
+use super::{Adapter, repo_script};
+use crate::config::{env_flag, normalize_newlines};
+use crate::hooks::GIT_HOOKS;
 
 /// Adapter for the [husky](https://typicode.github.io/husky/) hook manager.
 ///
 /// Detects a `.husky/` directory in the repo root and generates a lefthook
 /// command that executes `.husky/<hook>` if the corresponding script exists.
+/// With `LHM_HUSKY_SPLIT=1`, each line of the script becomes its own command
+/// (`husky-1`, `husky-2`, ...) instead.
 pub struct HuskyAdapter;
 
 impl Adapter for HuskyAdapter {
@@ -19,14 +24,146 @@ impl Adapter for HuskyAdapter {
     }
 
     fn generate_config(&self, root: &Path, hook_name: &str) -> Option<Value> {
-        let script = root.join(".husky").join(hook_name);
-        if !script.is_file() {
+        generate(root, hook_name, env_flag("LHM_HUSKY_SPLIT"))
+    }
+
+    fn supported_hooks(&self, root: &Path) -> Vec<String> {
+        let husky_dir = root.join(".husky");
+        GIT_HOOKS
+            .iter()
+            .filter(|h| husky_dir.join(h).is_file())
+            .map(|h| h.to_string())
+            .collect()
+    }
+
+    fn detection_markers(&self) -> &'static [&'static str] {
+        &[".husky"]
+    }
+
+    /// `.husky/` itself (for scripts being added or removed) and each hook script in it.
+    fn config_sources(&self, root: &Path) -> Vec<PathBuf> {
+        let husky_dir = root.join(".husky");
+        let scripts = self.supported_hooks(root).into_iter().map(|h| husky_dir.join(h));
+        std::iter::once(husky_dir.clone()).chain(scripts).collect()
+    }
+}
+
+/// Build the config for `.husky/<hook_name>`, one command per script line when `split` is set.
+fn generate(root: &Path, hook_name: &str, split: bool) -> Option<Value> {
+    let script = root.join(".husky").join(hook_name);
+    if !script.is_file() {
+        return None;
+    }
+
+    let mut commands = Mapping::new();
+    let lines = if split {
+        fs::read_to_string(&script)
+            .ok()
+            .and_then(|c| split_script(&normalize_newlines(&c)))
+    } else {
+        None
+    };
+    match lines {
+        Some(lines) => {
+            for (i, line) in lines.into_iter().enumerate() {
+                commands.insert(format!("husky-{}", i + 1).into(), run_command(line));
+            }
+        }
+        None => {
+            commands.insert("husky".into(), run_command(repo_script(&format!(".husky/{hook_name}"))));
+        }
+    }
+    let mut hook = Mapping::new();
+    hook.insert("commands".into(), Value::Mapping(commands));
+    let mut config = Mapping::new();
+    config.insert(hook_name.into(), Value::Mapping(hook));
+    Some(Value::Mapping(config))
+}
+
+fn run_command(run: String) -> Value {
+    let mut command = Mapping::new();
+    command.insert("run".into(), run.into());
+    Value::Mapping(command)
+}
+
+/// First words of lines that only work together with other lines of the
+/// script: parts of compound commands, and commands changing the state (cwd,
+/// environment, options, control flow) the following lines run in.
+const UNSPLITTABLE_WORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "for", "while", "until", "do", "done", "case", "esac", "select", "function",
+    "{", "}", "(", ")", "cd", "pushd", "popd", "export", "unset", "set", "source", ".", "alias", "umask", "shift",
+    "exec", "trap", "readonly", "local", "declare", "typeset", "eval", "exit", "return",
+];
+
+/// Split a husky script into one command per line, dropping the shebang,
+/// comments, blank lines and the husky v8 `. "$(dirname -- "$0")/_/husky.sh"`
+/// boilerplate.
+///
+/// Returns `None` when the script can't be split safely: it has no commands,
+/// it reads the hook's positional arguments (`$1`, `$@`, ...), which only
+/// exist when the script runs as a whole, or it has a line that isn't a
+/// self-contained command (see `splittable_line`).
+fn split_script(content: &str) -> Option<Vec<String>> {
+    let mut lines = Vec::new();
+    for raw in content.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') || (line.starts_with(". ") && line.contains("husky.sh")) {
+            continue;
+        }
+        if !splittable_line(line) {
             return None;
         }
+        lines.push(line.to_string());
+    }
+    let uses_args = lines.iter().any(|l| {
+        ["$1", "$2", "$3", "$@", "$*", "${1", "${2", "${3"]
+            .iter()
+            .any(|a| l.contains(a))
+    });
+    if lines.is_empty() || uses_args {
+        return None;
+    }
+    Some(lines)
+}
 
-        let config = format!("{hook_name}:\n  commands:\n    husky:\n      run: .husky/{hook_name}\n");
-        serde_yaml::from_str(&config).ok()
+/// Whether `line` can run as a command of its own: it doesn't open, continue
+/// or close a multi-line construct (compound commands, functions, heredocs,
+/// `\\` or trailing `|`/`&&` continuations) and doesn't change state for the
+/// lines after it (`cd`, `export`, a bare `VAR=value`, `exit`, ...).
+fn splittable_line(line: &str) -> bool {
+    if line.contains("<<") || line.contains("()") || line.ends_with(['\\', '|', '&', '{', '(']) {
+        return false;
+    }
+    // Unbalanced quotes continue on the next line
+    let Ok(words) = crate::shell_words(line) else {
+        return false;
+    };
+    // The first word of every command in the line, after `VAR=value` prefixes
+    let mut command_start = true;
+    let mut assignments_only = true;
+    for word in &words {
+        let separator = matches!(word.as_str(), "&&" | "||" | "|" | ";") || word.ends_with(';');
+        let bare = word.trim_end_matches(';');
+        if command_start && !is_assignment(bare) {
+            if UNSPLITTABLE_WORDS.contains(&bare) {
+                return false;
+            }
+            command_start = false;
+            assignments_only = false;
+        }
+        if separator {
+            command_start = true;
+        }
     }
+    !assignments_only
+}
+
+/// `NAME=value`, which sets a shell variable when it is a whole command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+            && name.chars().all(|c| c == '_' || c.is_ascii_alphanumeric())
+    })
 }
 
 #[cfg(test)]
@@ -38,6 +175,21 @@ mod tests {
         HuskyAdapter
     }
 
+    #[test]
+    fn test_config_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let husky = dir.path().join(".husky");
+        fs::create_dir_all(husky.join("_")).unwrap();
+        fs::write(husky.join("pre-commit"), "npm test\n").unwrap();
+        fs::write(husky.join("commit-msg"), "commitlint --edit\n").unwrap();
+        fs::write(husky.join("notes.txt"), "not a hook\n").unwrap();
+
+        assert_eq!(
+            adapter().config_sources(dir.path()),
+            [husky.clone(), husky.join("commit-msg"), husky.join("pre-commit")]
+        );
+    }
+
     #[test]
     fn test_detect_with_husky_dir() {
         let dir = tempfile::tempdir().unwrap();
@@ -71,6 +223,87 @@ mod tests {
         assert!(out.contains(".husky/pre-commit"), "has run command: {out}");
     }
 
+    #[test]
+    fn test_split_script_strips_boilerplate() {
+        let script = "#!/usr/bin/env sh\n. \"$(dirname -- \"$0\")/_/husky.sh\"\n\n# lint first\nnpm run lint\nFORCE_COLOR=1 npx prettier --check .\nnpm test\n";
+        assert_eq!(
+            split_script(script).unwrap(),
+            ["npm run lint", "FORCE_COLOR=1 npx prettier --check .", "npm test"]
+        );
+    }
+
+    #[test]
+    fn test_split_script_keeps_compound_commands_whole() {
+        let scripts = [
+            "if [ -f package.json ]; then\n  npm test\nfi\n",
+            "for d in a b; do\n  make -C $d\ndone\n",
+            "while read f; do\n  lint \"$f\"\ndone < files\n",
+            "case \"$CI\" in\n  true) npm run ci ;;\n  *) npm test ;;\nesac\n",
+            "check() {\n  npm test\n}\ncheck\n",
+            "cat <<EOF > msg\nhello\nEOF\nnpm test\n",
+            "npx prettier \\\n  --check .\nnpm test\n",
+            "npm run build &&\n  npm test\n",
+        ];
+        for script in scripts {
+            assert!(split_script(script).is_none(), "{script:?}");
+        }
+    }
+
+    #[test]
+    fn test_split_script_keeps_state_changes_whole() {
+        let scripts = [
+            "cd frontend\nnpm test\n",
+            "export NODE_ENV=test\nnpm test\n",
+            "NODE_ENV=test\nnpm test\n",
+            "set -e\nnpm run lint\nnpm test\n",
+            "source .env\nnpm test\n",
+            "[ -n \"$CI\" ] && exit 0\nnpm test\n",
+            "exit 0\nnpm test\n",
+        ];
+        for script in scripts {
+            assert!(split_script(script).is_none(), "{script:?}");
+        }
+    }
+
+    #[test]
+    fn test_split_script_keeps_scripts_using_args_whole() {
+        assert!(split_script("npx commitlint --edit $1\n").is_none());
+        assert!(split_script("#!/bin/sh\n# nothing here\n").is_none());
+    }
+
+    #[test]
+    fn test_generate_config_split() {
+        let dir = tempfile::tempdir().unwrap();
+        let husky_dir = dir.path().join(".husky");
+        fs::create_dir_all(&husky_dir).unwrap();
+        fs::write(husky_dir.join("pre-commit"), "npm run lint\nnpm test\n").unwrap();
+
+        let config = generate(dir.path(), "pre-commit", true).unwrap();
+        let cmds = &config["pre-commit"]["commands"];
+        assert_eq!(cmds["husky-1"]["run"].as_str(), Some("npm run lint"));
+        assert_eq!(cmds["husky-2"]["run"].as_str(), Some("npm test"));
+        assert!(cmds.get("husky").is_none());
+    }
+
+    #[test]
+    fn test_generate_config_split_crlf() {
+        let dir = tempfile::tempdir().unwrap();
+        let husky_dir = dir.path().join(".husky");
+        fs::create_dir_all(&husky_dir).unwrap();
+        fs::write(
+            husky_dir.join("pre-commit"),
+            "#!/usr/bin/env sh\r\nnpx prettier --check .\r\nnpm test\r\n",
+        )
+        .unwrap();
+
+        let config = generate(dir.path(), "pre-commit", true).unwrap();
+        let out = serde_yaml::to_string(&config).unwrap();
+        assert!(!out.contains('\r'), "no carriage returns: {out:?}");
+        let cmds = &config["pre-commit"]["commands"];
+        assert_eq!(cmds["husky-1"]["run"].as_str(), Some("npx prettier --check ."));
+        assert_eq!(cmds["husky-2"]["run"].as_str(), Some("npm test"));
+    }
+
     #[test]
     fn test_generate_config_without_hook_script() {
         let dir = tempfile::tempdir().unwrap();
@@ -80,6 +313,18 @@ mod tests {
         assert!(adapter().generate_config(dir.path(), "pre-commit").is_none());
     }
 
+    #[test]
+    fn test_supported_hooks_only_existing_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let husky_dir = dir.path().join(".husky");
+        fs::create_dir_all(husky_dir.join("_")).unwrap();
+        fs::write(husky_dir.join("pre-push"), "#!/bin/sh\n").unwrap();
+        fs::write(husky_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(husky_dir.join("README.md"), "not a hook\n").unwrap();
+
+        assert_eq!(adapter().supported_hooks(dir.path()), ["pre-commit", "pre-push"]);
+    }
+
     #[test]
     fn test_generate_config_different_hooks() {
         let dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,236 @@
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Adapter, marker_paths};
+use crate::config::normalize_newlines;
+use log::warn;
+
+/// Standalone config files, in the order lint-staged looks for them. JSON is
+/// parsed as YAML. JavaScript configs (`lint-staged.config.js`, ...) can't be
+/// evaluated and aren't read.
+const RC_PATHS: &[&str] = &[
+    ".lintstagedrc",
+    ".lintstagedrc.json",
+    ".lintstagedrc.yaml",
+    ".lintstagedrc.yml",
+];
+
+/// Files whose presence makes the adapter apply: `RC_PATHS` plus `package.json`
+/// (for its `lint-staged` key).
+static DETECTION_MARKERS: [&str; RC_PATHS.len() + 1] = marker_paths(RC_PATHS, &["package.json"]);
+
+/// The only git hook lint-staged is meant for.
+const HOOK: &str = "pre-commit";
+
+/// Adapter for [lint-staged](https://github.com/lint-staged/lint-staged) configs.
+///
+/// Reads the first `.lintstagedrc*` file, or the `lint-staged` key of
+/// `package.json`, and turns each glob → command pair into a `pre-commit`
+/// command with the glob as its lefthook `glob` and `{staged_files}` appended.
+pub struct LintStagedAdapter;
+
+/// One `glob: command` pair. A glob mapped to a list yields one task per entry.
+struct Task {
+    glob: String,
+    run: String,
+}
+
+/// Parse a lint-staged config mapping (glob to a command or list of commands).
+fn tasks_from(config: &Value) -> Option<Vec<Task>> {
+    let Value::Mapping(globs) = config else {
+        return None;
+    };
+    let mut tasks = Vec::new();
+    for (glob, commands) in globs {
+        let Some(glob) = glob.as_str() else {
+            continue;
+        };
+        let commands = match commands {
+            Value::String(run) => vec![run.as_str()],
+            Value::Sequence(runs) => runs.iter().filter_map(Value::as_str).collect(),
+            _ => {
+                warn!("ignoring lint-staged entry for {glob}: expected a command or list of commands");
+                continue;
+            }
+        };
+        tasks.extend(commands.into_iter().map(|run| Task {
+            glob: glob.to_string(),
+            run: run.to_string(),
+        }));
+    }
+    Some(tasks)
+}
+
+/// The repo's lint-staged config: the first rc file that exists, otherwise
+/// the `lint-staged` key of `package.json`.
+fn load_tasks(root: &Path) -> Option<Vec<Task>> {
+    if let Some(path) = RC_PATHS.iter().map(|p| root.join(p)).find(|p| p.is_file()) {
+        let content = fs::read_to_string(&path).ok()?;
+        return match serde_yaml::from_str(&normalize_newlines(&content)) {
+            Ok(config) => tasks_from(&config),
+            Err(e) => {
+                warn!("ignoring {}: {e}", path.display());
+                None
+            }
+        };
+    }
+    let content = fs::read_to_string(root.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let config = serde_yaml::to_value(package.get("lint-staged")?).ok()?;
+    tasks_from(&config)
+}
+
+impl Adapter for LintStagedAdapter {
+    fn name(&self) -> &str {
+        "lint-staged"
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        load_tasks(root).is_some_and(|tasks| !tasks.is_empty())
+    }
+
+    fn generate_config(&self, root: &Path, hook_name: &str) -> Option<Value> {
+        if hook_name != HOOK {
+            return None;
+        }
+        let tasks = load_tasks(root)?;
+        if tasks.is_empty() {
+            return None;
+        }
+
+        let mut commands = Mapping::new();
+        for (i, task) in tasks.into_iter().enumerate() {
+            let mut command = Mapping::new();
+            command.insert("run".into(), format!("{} {{staged_files}}", task.run).into());
+            command.insert("glob".into(), task.glob.into());
+            // lefthook orders commands by name otherwise, and lint-staged runs a
+            // glob's commands in sequence
+            command.insert("priority".into(), (i + 1).into());
+            commands.insert(format!("lint-staged-{}", i + 1).into(), Value::Mapping(command));
+        }
+        let mut hook = Mapping::new();
+        hook.insert("commands".into(), Value::Mapping(commands));
+        let mut config = Mapping::new();
+        config.insert(HOOK.into(), Value::Mapping(hook));
+        Some(Value::Mapping(config))
+    }
+
+    fn supported_hooks(&self, _root: &Path) -> Vec<String> {
+        vec![HOOK.to_string()]
+    }
+
+    fn detection_markers(&self) -> &'static [&'static str] {
+        &DETECTION_MARKERS
+    }
+
+    fn config_sources(&self, root: &Path) -> Vec<PathBuf> {
+        DETECTION_MARKERS.iter().map(|p| root.join(p)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> LintStagedAdapter {
+        LintStagedAdapter
+    }
+
+    fn commands(root: &Path) -> Mapping {
+        let config = adapter().generate_config(root, "pre-commit").unwrap();
+        config["pre-commit"]["commands"].as_mapping().unwrap().clone()
+    }
+
+    #[test]
+    fn test_object_form_rc() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".lintstagedrc"),
+            r#"{"*.{js,ts}": "eslint --fix", "*.md": "prettier --write"}"#,
+        )
+        .unwrap();
+        assert!(adapter().detect(dir.path()));
+
+        let commands = commands(dir.path());
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands["lint-staged-1"]["run"].as_str(),
+            Some("eslint --fix {staged_files}")
+        );
+        assert_eq!(commands["lint-staged-1"]["glob"].as_str(), Some("*.{js,ts}"));
+        assert_eq!(
+            commands["lint-staged-2"]["run"].as_str(),
+            Some("prettier --write {staged_files}")
+        );
+        assert_eq!(commands["lint-staged-2"]["glob"].as_str(), Some("*.md"));
+        assert!(adapter().generate_config(dir.path(), "pre-push").is_none());
+    }
+
+    #[test]
+    fn test_array_form_yaml_rc() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".lintstagedrc.yml"),
+            "'*.ts':\n  - eslint --fix\n  - prettier --write\n'*.css': stylelint\n",
+        )
+        .unwrap();
+
+        let commands = commands(dir.path());
+        let runs: Vec<(&str, &str, u64)> = commands
+            .values()
+            .map(|c| {
+                (
+                    c["glob"].as_str().unwrap(),
+                    c["run"].as_str().unwrap(),
+                    c["priority"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("*.ts", "eslint --fix {staged_files}", 1),
+                ("*.ts", "prettier --write {staged_files}", 2),
+                ("*.css", "stylelint {staged_files}", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_json_key() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "x", "lint-staged": {"*.js": ["eslint"]}}"#,
+        )
+        .unwrap();
+        assert!(adapter().detect(dir.path()));
+        assert_eq!(
+            commands(dir.path())["lint-staged-1"]["run"].as_str(),
+            Some("eslint {staged_files}")
+        );
+    }
+
+    #[test]
+    fn test_rc_file_wins_over_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"lint-staged": {"*.js": "eslint"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join(".lintstagedrc.json"), r#"{"*.ts": "tsc-files"}"#).unwrap();
+        assert_eq!(commands(dir.path())["lint-staged-1"]["glob"].as_str(), Some("*.ts"));
+    }
+
+    #[test]
+    fn test_not_detected_without_config() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!adapter().detect(dir.path()));
+        fs::write(dir.path().join("package.json"), r#"{"scripts": {"test": "jest"}}"#).unwrap();
+        assert!(!adapter().detect(dir.path()));
+        fs::write(dir.path().join(".lintstagedrc"), "{}").unwrap();
+        assert!(!adapter().detect(dir.path()));
+    }
+}
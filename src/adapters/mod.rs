@@ -1,12 +1,19 @@
 mod hooks_dir;
 mod husky;
+mod lint_staged;
+mod package_json;
 mod pre_commit;
 
 use serde_yaml::Value;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::hooks::GIT_HOOKS;
+use crate::merge::merge_configs;
 
 pub use hooks_dir::HooksDirAdapter;
 pub use husky::HuskyAdapter;
+pub use lint_staged::LintStagedAdapter;
+pub use package_json::PackageJsonScriptsAdapter;
 pub use pre_commit::PreCommitAdapter;
 
 /// Adapter for translating third-party git hook managers into lefthook configs.
@@ -22,9 +29,112 @@ pub trait Adapter {
 
     /// Generate a lefthook config `Value` for the given hook name.
     ///
+    /// Besides the hook section, the config may set top-level lefthook
+    /// settings (e.g. `skip_lfs`, `colors`); they merge like repo settings and
+    /// are left alone by source marking and annotation.
+    ///
     /// Returns `None` if this adapter has nothing to run for the given hook
     /// (e.g. no matching hook script exists).
     fn generate_config(&self, root: &Path, hook_name: &str) -> Option<Value>;
+
+    /// Hook names this adapter may produce config for, in `GIT_HOOKS` order.
+    /// Custom hook names that git never runs, such as pre-commit's `manual`,
+    /// may follow the `GIT_HOOKS` entries, so not every name is a git hook.
+    ///
+    /// Used to narrow the all-hooks loop (e.g. for `dry-run`). Defaults to every
+    /// hook in `GIT_HOOKS`.
+    fn supported_hooks(&self, _root: &Path) -> Vec<String> {
+        GIT_HOOKS.iter().map(|h| h.to_string()).collect()
+    }
+
+    /// Prepare per-repo state for generating config for several hooks.
+    ///
+    /// Adapters that parse a source config override this so the file is read
+    /// once rather than once per hook. Defaults to delegating to
+    /// `supported_hooks`/`generate_config`.
+    fn prepare<'a>(&'a self, root: &'a Path) -> Box<dyn PreparedAdapter + 'a> {
+        Box::new(Unprepared { adapter: self, root })
+    }
+
+    /// Generate the config for every supported hook at once, as a single
+    /// multi-hook config. Returns `None` if no hook has anything to run.
+    ///
+    /// Defaults to merging the per-hook configs of a `prepare`d adapter.
+    /// Adapters that can translate their source in one pass override this.
+    fn generate_all(&self, root: &Path) -> Option<Value> {
+        let prepared = self.prepare(root);
+        let mut combined: Option<Value> = None;
+        for name in prepared.supported_hooks() {
+            if let Some(config) = prepared.generate_config(&name) {
+                combined = Some(match combined {
+                    Some(existing) => merge_configs(existing, config),
+                    None => config,
+                });
+            }
+        }
+        combined
+    }
+
+    /// Files and directories under `root` whose contents the generated config
+    /// depends on, for change detection. Includes paths that don't exist yet
+    /// when creating them would change the output. Defaults to none.
+    fn config_sources(&self, _root: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Paths relative to the repo root that `detect` looks for, for `lhm explain`.
+    fn detection_markers(&self) -> &'static [&'static str];
+}
+
+/// An adapter bound to one repo root by `Adapter::prepare`.
+pub trait PreparedAdapter {
+    /// Same as `Adapter::supported_hooks` for the prepared root.
+    fn supported_hooks(&self) -> Vec<String>;
+
+    /// Same as `Adapter::generate_config` for the prepared root.
+    fn generate_config(&self, hook_name: &str) -> Option<Value>;
+}
+
+/// Default `PreparedAdapter` that keeps no state of its own.
+struct Unprepared<'a, A: ?Sized> {
+    adapter: &'a A,
+    root: &'a Path,
+}
+
+impl<A: Adapter + ?Sized> PreparedAdapter for Unprepared<'_, A> {
+    fn supported_hooks(&self) -> Vec<String> {
+        self.adapter.supported_hooks(self.root)
+    }
+
+    fn generate_config(&self, hook_name: &str) -> Option<Value> {
+        self.adapter.generate_config(self.root, hook_name)
+    }
+}
+
+/// Shell word for a script at `rel` (relative to the repo root) that resolves
+/// regardless of the directory lefthook runs the command from.
+///
+/// The root is looked up at run time rather than baked in, so the generated
+/// config stays valid if the repo moves (or is written out by `lhm migrate`).
+fn repo_script(rel: &str) -> String {
+    format!("\"$(git rev-parse --show-toplevel)/{rel}\"")
+}
+
+/// `paths` followed by `extra`, for building an adapter's detection markers
+/// from its config path list. `N` must be the combined length.
+const fn marker_paths<const N: usize>(paths: &[&'static str], extra: &[&'static str]) -> [&'static str; N] {
+    assert!(paths.len() + extra.len() == N);
+    let mut markers = [""; N];
+    let mut i = 0;
+    while i < N {
+        markers[i] = if i < paths.len() {
+            paths[i]
+        } else {
+            extra[i - paths.len()]
+        };
+        i += 1;
+    }
+    markers
 }
 
 /// All known adapters, in priority order.
@@ -32,25 +142,76 @@ fn all_adapters() -> Vec<Box<dyn Adapter>> {
     vec![
         Box::new(PreCommitAdapter),
         Box::new(HuskyAdapter),
+        Box::new(LintStagedAdapter),
         Box::new(HooksDirAdapter),
+        Box::new(PackageJsonScriptsAdapter),
     ]
 }
 
-/// Detect the first applicable adapter for the given repo root.
-pub fn detect_adapter(root: &Path) -> Option<Box<dyn Adapter>> {
-    all_adapters().into_iter().find(|a| a.detect(root))
+/// Look up an adapter by its `name()`, e.g. when pinned in `.lhm.yml`.
+pub fn adapter_by_name(name: &str) -> Result<Box<dyn Adapter>, String> {
+    let adapters = all_adapters();
+    let known: Vec<String> = adapters.iter().map(|a| a.name().to_string()).collect();
+    adapters
+        .into_iter()
+        .find(|a| a.name() == name)
+        .ok_or_else(|| format!("unknown adapter '{name}' (known: {})", known.join(", ")))
+}
+
+/// Why each adapter was or wasn't chosen for `root`, one greppable
+/// `<adapter>: ...` line per adapter in priority order, then a `selected:`
+/// line naming the adapter that would be used.
+pub fn explain(root: &Path, disabled: &[String], pinned: Option<&str>) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut selected = None;
+    for adapter in all_adapters() {
+        let markers = adapter.detection_markers();
+        let found: Vec<&str> = markers.iter().copied().filter(|m| root.join(m).exists()).collect();
+        let detected = adapter.detect(root);
+        let mut line = match (detected, found.is_empty()) {
+            (true, _) => format!("{}: detected (found {})", adapter.name(), found.join(", ")),
+            (false, true) => format!("{}: not detected (missing {})", adapter.name(), markers.join(", ")),
+            (false, false) => format!(
+                "{}: not detected (found {}, but it defines nothing to run)",
+                adapter.name(),
+                found.join(", ")
+            ),
+        };
+        if disabled.iter().any(|d| d == adapter.name()) {
+            line.push_str(", disabled by lhm.disable_adapters");
+        } else if detected && selected.is_none() {
+            selected = Some(adapter.name().to_string());
+        }
+        lines.push(line);
+    }
+    lines.push(match (pinned, selected) {
+        (Some(name), _) => format!("selected: {name} (pinned in .lhm.yml)"),
+        (None, Some(name)) => format!("selected: {name} (first detected in priority order)"),
+        (None, None) => "selected: none".to_string(),
+    });
+    lines
+}
+
+/// Detect the first applicable adapter for the given repo root, skipping
+/// adapters whose name is in `disabled`.
+pub fn detect_adapter(root: &Path, disabled: &[String]) -> Option<Box<dyn Adapter>> {
+    all_adapters()
+        .into_iter()
+        .filter(|a| !disabled.iter().any(|d| d == a.name()))
+        .find(|a| a.detect(root))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
+    use std::process::Command;
 
     #[test]
     fn test_detect_adapter_pre_commit() {
         let dir = tempfile::tempdir().unwrap();
         fs::write(dir.path().join(".pre-commit-config.yaml"), "repos: []\n").unwrap();
-        let adapter = detect_adapter(dir.path()).unwrap();
+        let adapter = detect_adapter(dir.path(), &[]).unwrap();
         assert_eq!(adapter.name(), "pre-commit");
     }
 
@@ -58,7 +219,7 @@ mod tests {
     fn test_detect_adapter_husky() {
         let dir = tempfile::tempdir().unwrap();
         fs::create_dir_all(dir.path().join(".husky")).unwrap();
-        let adapter = detect_adapter(dir.path()).unwrap();
+        let adapter = detect_adapter(dir.path(), &[]).unwrap();
         assert_eq!(adapter.name(), "husky");
     }
 
@@ -66,14 +227,153 @@ mod tests {
     fn test_detect_adapter_hooks_dir() {
         let dir = tempfile::tempdir().unwrap();
         fs::create_dir_all(dir.path().join(".hooks")).unwrap();
-        let adapter = detect_adapter(dir.path()).unwrap();
+        let adapter = detect_adapter(dir.path(), &[]).unwrap();
+        assert_eq!(adapter.name(), "hooks-dir");
+    }
+
+    #[test]
+    fn test_repo_script_runs_from_any_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(
+            Command::new("git")
+                .arg("init")
+                .arg("-q")
+                .arg(root)
+                .status()
+                .unwrap()
+                .success()
+        );
+        fs::create_dir_all(root.join(".hooks")).unwrap();
+        fs::write(root.join(".hooks").join("pre-commit"), "").unwrap();
+        let sub = root.join("nested").join("dir");
+        fs::create_dir_all(&sub).unwrap();
+
+        let check = format!("test -f {}", repo_script(".hooks/pre-commit"));
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&check)
+            .current_dir(&sub)
+            .status()
+            .unwrap();
+        assert!(status.success(), "{check}");
+    }
+
+    #[test]
+    fn test_default_generate_all_combines_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks/pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join(".hooks/pre-push"), "#!/bin/sh\n").unwrap();
+
+        let adapter = HooksDirAdapter;
+        let all = adapter.generate_all(dir.path()).unwrap();
+        let hooks: Vec<&str> = all.as_mapping().unwrap().keys().filter_map(Value::as_str).collect();
+        assert_eq!(hooks, ["pre-commit", "pre-push"]);
+        assert_eq!(
+            all["pre-push"],
+            adapter.generate_config(dir.path(), "pre-push").unwrap()["pre-push"]
+        );
+    }
+
+    #[test]
+    fn test_detect_adapter_skips_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        assert!(detect_adapter(dir.path(), &["hooks-dir".to_string()]).is_none());
+
+        fs::create_dir_all(dir.path().join(".husky")).unwrap();
+        let adapter = detect_adapter(dir.path(), &["husky".to_string()]).unwrap();
         assert_eq!(adapter.name(), "hooks-dir");
     }
 
+    #[test]
+    fn test_detect_adapter_package_json_after_hooks_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"pre-commit": "lint-staged"}}"#,
+        )
+        .unwrap();
+        assert_eq!(detect_adapter(dir.path(), &[]).unwrap().name(), "package-json");
+
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        assert_eq!(detect_adapter(dir.path(), &[]).unwrap().name(), "hooks-dir");
+    }
+
+    #[test]
+    fn test_adapter_by_name() {
+        assert_eq!(adapter_by_name("husky").unwrap().name(), "husky");
+        let err = adapter_by_name("overcommit").err().unwrap();
+        assert_eq!(
+            err,
+            "unknown adapter 'overcommit' (known: pre-commit, husky, lint-staged, hooks-dir, package-json)"
+        );
+    }
+
+    #[test]
+    fn test_explain_priority_winner() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".pre-commit-config.yaml"), "repos: []\n").unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        assert_eq!(
+            explain(dir.path(), &[], None),
+            [
+                "pre-commit: detected (found .pre-commit-config.yaml)",
+                "husky: not detected (missing .husky)",
+                "lint-staged: not detected (missing .lintstagedrc, .lintstagedrc.json, .lintstagedrc.yaml, .lintstagedrc.yml, package.json)",
+                "hooks-dir: detected (found .hooks)",
+                "package-json: not detected (missing package.json)",
+                "selected: pre-commit (first detected in priority order)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_disabled_pinned_and_unqualified() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".husky")).unwrap();
+        fs::create_dir_all(dir.path().join("git-hooks")).unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"scripts": {"build": "tsc"}}"#).unwrap();
+
+        let lines = explain(dir.path(), &["husky".to_string()], None);
+        assert_eq!(
+            lines[1],
+            "husky: detected (found .husky), disabled by lhm.disable_adapters"
+        );
+        assert_eq!(
+            lines[2],
+            "lint-staged: not detected (found package.json, but it defines nothing to run)"
+        );
+        assert_eq!(lines[3], "hooks-dir: detected (found git-hooks)");
+        assert_eq!(
+            lines[4],
+            "package-json: not detected (found package.json, but it defines nothing to run)"
+        );
+        assert_eq!(lines[5], "selected: hooks-dir (first detected in priority order)");
+
+        let lines = explain(dir.path(), &[], Some("package-json"));
+        assert_eq!(lines[5], "selected: package-json (pinned in .lhm.yml)");
+
+        let empty = tempfile::tempdir().unwrap();
+        assert_eq!(explain(empty.path(), &[], None).last().unwrap(), "selected: none");
+    }
+
     #[test]
     fn test_detect_adapter_none() {
         let dir = tempfile::tempdir().unwrap();
-        assert!(detect_adapter(dir.path()).is_none());
+        assert!(detect_adapter(dir.path(), &[]).is_none());
+    }
+
+    #[test]
+    fn test_detect_adapter_lint_staged_after_husky() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".lintstagedrc"), r#"{"*.js": "eslint"}"#).unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        assert_eq!(detect_adapter(dir.path(), &[]).unwrap().name(), "lint-staged");
+
+        fs::create_dir_all(dir.path().join(".husky")).unwrap();
+        assert_eq!(detect_adapter(dir.path(), &[]).unwrap().name(), "husky");
     }
 
     #[test]
@@ -81,7 +381,7 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         fs::write(dir.path().join(".pre-commit-config.yaml"), "repos: []\n").unwrap();
         fs::create_dir_all(dir.path().join(".husky")).unwrap();
-        let adapter = detect_adapter(dir.path()).unwrap();
+        let adapter = detect_adapter(dir.path(), &[]).unwrap();
         assert_eq!(adapter.name(), "pre-commit");
     }
 }
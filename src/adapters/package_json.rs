@@ -0,0 +1,167 @@
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::Adapter;
+use crate::hooks::GIT_HOOKS;
+
+/// Adapter for hook-named scripts in `package.json`.
+///
+/// Detects a `package.json` whose `scripts` object defines at least one git
+/// hook name (e.g. `"pre-commit": "lint-staged"`) and generates a lefthook
+/// command that runs it through the repo's package manager: `yarn <hook>` when
+/// a `yarn.lock` exists, `npm run <hook>` otherwise.
+pub struct PackageJsonScriptsAdapter;
+
+/// Hook names defined in the `scripts` object of `<root>/package.json`.
+fn hook_scripts(root: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+    let Some(scripts) = package.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+    GIT_HOOKS
+        .iter()
+        .filter(|h| scripts.get(**h).is_some_and(|s| s.is_string()))
+        .map(|h| h.to_string())
+        .collect()
+}
+
+/// Command that runs the `script` from `package.json`, picking the package
+/// manager by lockfile.
+fn run_script(root: &Path, script: &str) -> String {
+    if root.join("yarn.lock").is_file() {
+        format!("yarn {script}")
+    } else {
+        format!("npm run {script}")
+    }
+}
+
+impl Adapter for PackageJsonScriptsAdapter {
+    fn name(&self) -> &str {
+        "package-json"
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        !hook_scripts(root).is_empty()
+    }
+
+    fn generate_config(&self, root: &Path, hook_name: &str) -> Option<Value> {
+        if !hook_scripts(root).iter().any(|h| h == hook_name) {
+            return None;
+        }
+
+        let mut command = Mapping::new();
+        command.insert("run".into(), run_script(root, hook_name).into());
+        let mut commands = Mapping::new();
+        commands.insert("package-json".into(), Value::Mapping(command));
+        let mut hook = Mapping::new();
+        hook.insert("commands".into(), Value::Mapping(commands));
+        let mut config = Mapping::new();
+        config.insert(hook_name.into(), Value::Mapping(hook));
+        Some(Value::Mapping(config))
+    }
+
+    fn supported_hooks(&self, root: &Path) -> Vec<String> {
+        hook_scripts(root)
+    }
+
+    fn detection_markers(&self) -> &'static [&'static str] {
+        &["package.json"]
+    }
+
+    /// `package.json`, plus `yarn.lock` since it picks the package manager.
+    fn config_sources(&self, root: &Path) -> Vec<PathBuf> {
+        vec![root.join("package.json"), root.join("yarn.lock")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> PackageJsonScriptsAdapter {
+        PackageJsonScriptsAdapter
+    }
+
+    fn write_package(dir: &Path, scripts: &str) {
+        fs::write(
+            dir.join("package.json"),
+            format!(r#"{{"name": "x", "scripts": {scripts}}}"#),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_config_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            adapter().config_sources(dir.path()),
+            [dir.path().join("package.json"), dir.path().join("yarn.lock")]
+        );
+    }
+
+    #[test]
+    fn test_detect_with_hook_script() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"pre-commit": "lint-staged", "build": "tsc"}"#);
+        assert!(adapter().detect(dir.path()));
+    }
+
+    #[test]
+    fn test_detect_without_hook_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"build": "tsc", "test": "jest"}"#);
+        assert!(!adapter().detect(dir.path()));
+    }
+
+    #[test]
+    fn test_detect_without_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!adapter().detect(dir.path()));
+    }
+
+    #[test]
+    fn test_generate_config_npm() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"pre-commit": "lint-staged"}"#);
+        fs::write(dir.path().join("package-lock.json"), "{}").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        assert_eq!(
+            config["pre-commit"]["commands"]["package-json"]["run"].as_str(),
+            Some("npm run pre-commit")
+        );
+    }
+
+    #[test]
+    fn test_generate_config_yarn_lockfile() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"pre-push": "yarn test"}"#);
+        fs::write(dir.path().join("yarn.lock"), "").unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-push").unwrap();
+        assert_eq!(
+            config["pre-push"]["commands"]["package-json"]["run"].as_str(),
+            Some("yarn pre-push")
+        );
+    }
+
+    #[test]
+    fn test_generate_config_missing_script() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"pre-commit": "lint-staged"}"#);
+        assert!(adapter().generate_config(dir.path(), "pre-push").is_none());
+    }
+
+    #[test]
+    fn test_supported_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), r#"{"pre-push": "a", "commit-msg": "b", "lint": "c"}"#);
+        assert_eq!(adapter().supported_hooks(dir.path()), ["commit-msg", "pre-push"]);
+    }
+}
@@ -1,9 +1,28 @@
 use serde::Deserialize;
 use serde_yaml::{Mapping, Value};
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use super::Adapter;
+use super::{Adapter, PreparedAdapter, marker_paths};
+use crate::config::normalize_newlines;
+use crate::hooks::GIT_HOOKS;
+use log::warn;
+
+/// Config locations relative to the repo root, in priority order.
+const CONFIG_PATHS: &[&str] = &[
+    ".pre-commit-config.yaml",
+    ".config/pre-commit-config.yaml",
+    ".config/pre-commit/config.yaml",
+];
+
+/// Optional machine-specific config read alongside the main one. Its local
+/// hooks replace main-config local hooks with the same id.
+const LOCAL_CONFIG_PATH: &str = ".pre-commit-config.local.yaml";
+
+/// Files whose presence makes the adapter apply: `CONFIG_PATHS` plus
+/// `LOCAL_CONFIG_PATH`.
+static DETECTION_MARKERS: [&str; CONFIG_PATHS.len() + 1] = marker_paths(CONFIG_PATHS, &[LOCAL_CONFIG_PATH]);
 
 /// Adapter for the [pre-commit](https://pre-commit.com/) hook manager.
 ///
@@ -12,46 +31,209 @@ use super::Adapter;
 /// in the remote `.pre-commit-hooks.yaml` and can't be resolved without cloning.
 pub struct PreCommitAdapter;
 
+/// Return the first pre-commit config file that exists under `root`.
+fn find_config_file(root: &Path) -> Option<PathBuf> {
+    CONFIG_PATHS.iter().map(|p| root.join(p)).find(|p| p.is_file())
+}
+
 impl Adapter for PreCommitAdapter {
     fn name(&self) -> &str {
         "pre-commit"
     }
 
     fn detect(&self, root: &Path) -> bool {
-        root.join(".pre-commit-config.yaml").is_file()
+        find_config_file(root).is_some() || root.join(LOCAL_CONFIG_PATH).is_file()
     }
 
     fn generate_config(&self, root: &Path, hook_name: &str) -> Option<Value> {
-        let content = fs::read_to_string(root.join(".pre-commit-config.yaml")).ok()?;
-        let config: PreCommitConfig = serde_yaml::from_str(&content).ok()?;
+        generate_from(&load_config(root)?, hook_name)
+    }
+
+    /// Only the git hooks that at least one local hook is staged for.
+    fn supported_hooks(&self, root: &Path) -> Vec<String> {
+        load_config(root).map(|c| supported_from(&c)).unwrap_or_default()
+    }
+
+    fn prepare<'a>(&'a self, root: &'a Path) -> Box<dyn PreparedAdapter + 'a> {
+        Box::new(prepare_with(root, |p| fs::read_to_string(p)))
+    }
+
+    fn generate_all(&self, root: &Path) -> Option<Value> {
+        generate_all_from(&load_config(root)?)
+    }
+
+    fn detection_markers(&self) -> &'static [&'static str] {
+        &DETECTION_MARKERS
+    }
+
+    /// Every candidate config path, since creating an earlier one changes which
+    /// is read, plus the local config.
+    fn config_sources(&self, root: &Path) -> Vec<PathBuf> {
+        DETECTION_MARKERS.iter().map(|p| root.join(p)).collect()
+    }
+}
+
+/// A repo's pre-commit config, parsed once for all hooks.
+struct PreparedPreCommit {
+    config: Option<PreCommitConfig>,
+}
+
+impl PreparedAdapter for PreparedPreCommit {
+    fn supported_hooks(&self) -> Vec<String> {
+        self.config.as_ref().map(supported_from).unwrap_or_default()
+    }
+
+    fn generate_config(&self, hook_name: &str) -> Option<Value> {
+        generate_from(self.config.as_ref()?, hook_name)
+    }
+}
+
+fn prepare_with(root: &Path, read: impl Fn(&Path) -> io::Result<String>) -> PreparedPreCommit {
+    PreparedPreCommit {
+        config: load_config_with(root, read),
+    }
+}
+
+fn generate_from(config: &PreCommitConfig, hook_name: &str) -> Option<Value> {
+    let mut commands = Mapping::new();
+    let mut fail_fast = config.fail_fast;
+
+    for hook in local_hooks(config) {
+        if !hook_matches_stage(hook, &config.default_stages, hook_name) {
+            continue;
+        }
+        if let Some(cmd) = stage_command(hook, hook_name) {
+            fail_fast |= hook.fail_fast;
+            commands.insert(str_val(&hook.id), Value::Mapping(cmd));
+        }
+    }
 
-        let mut commands = Mapping::new();
+    let mut root_mapping = Mapping::new();
+    root_mapping.insert(str_val(hook_name), hook_mapping(commands, fail_fast)?);
+    Some(Value::Mapping(root_mapping))
+}
+
+/// Config for every stage in one pass over the local hooks, putting each hook
+/// into the buckets of the stages it runs for. Matches merging
+/// `generate_from` for each supported hook.
+fn generate_all_from(config: &PreCommitConfig) -> Option<Value> {
+    let stages: Vec<&str> = GIT_HOOKS.iter().copied().chain([MANUAL_STAGE]).collect();
+    let mut buckets = vec![(Mapping::new(), config.fail_fast); stages.len()];
 
-        for repo in &config.repos {
-            if repo.repo != "local" {
+    for hook in local_hooks(config) {
+        for (stage, (commands, fail_fast)) in stages.iter().zip(&mut buckets) {
+            if !hook_matches_stage(hook, &config.default_stages, stage) {
                 continue;
             }
-            for hook in &repo.hooks {
-                if !hook_matches_stage(hook, &config.default_stages, hook_name) {
-                    continue;
-                }
-                if let Some(cmd) = translate_hook(hook) {
-                    commands.insert(str_val(&hook.id), Value::Mapping(cmd));
-                }
+            if let Some(cmd) = stage_command(hook, stage) {
+                *fail_fast |= hook.fail_fast;
+                commands.insert(str_val(&hook.id), Value::Mapping(cmd));
             }
         }
+    }
 
-        if commands.is_empty() {
-            return None;
+    let mut root_mapping = Mapping::new();
+    for (stage, (commands, fail_fast)) in stages.into_iter().zip(buckets) {
+        if let Some(hook) = hook_mapping(commands, fail_fast) {
+            root_mapping.insert(str_val(stage), hook);
         }
+    }
+    (!root_mapping.is_empty()).then_some(Value::Mapping(root_mapping))
+}
+
+fn local_hooks(config: &PreCommitConfig) -> impl Iterator<Item = &Hook> {
+    config.repos.iter().filter(|r| r.repo == "local").flat_map(|r| &r.hooks)
+}
+
+/// `translate_hook` for the stage `hook_name`, tagged `manual` for the manual stage.
+fn stage_command(hook: &Hook, hook_name: &str) -> Option<Mapping> {
+    let mut cmd = translate_hook(hook, hook_name)?;
+    if hook_name == MANUAL_STAGE {
+        cmd.insert(str_val("tags"), Value::Sequence(vec![str_val(MANUAL_STAGE)]));
+    }
+    Some(cmd)
+}
+
+/// A lefthook hook mapping running `commands`, or `None` if there are none.
+fn hook_mapping(commands: Mapping, fail_fast: bool) -> Option<Value> {
+    if commands.is_empty() {
+        return None;
+    }
+    let mut hook_mapping = Mapping::new();
+    // lefthook has no per-command equivalent, so any `fail_fast` stops the
+    // whole hook on the first failure.
+    if fail_fast {
+        hook_mapping.insert(str_val("piped"), Value::Bool(true));
+    }
+    hook_mapping.insert(str_val("commands"), Value::Mapping(commands));
+    Some(Value::Mapping(hook_mapping))
+}
+
+fn supported_from(config: &PreCommitConfig) -> Vec<String> {
+    let local_hooks: Vec<&Hook> = local_hooks(config).collect();
+    GIT_HOOKS
+        .iter()
+        .chain([MANUAL_STAGE].iter())
+        .filter(|name| {
+            local_hooks
+                .iter()
+                .any(|hook| hook_matches_stage(hook, &config.default_stages, name))
+        })
+        .map(|name| name.to_string())
+        .collect()
+}
 
-        let mut hook_mapping = Mapping::new();
-        hook_mapping.insert(str_val("commands"), Value::Mapping(commands));
+/// Read and parse the repo's pre-commit config, if any.
+fn load_config(root: &Path) -> Option<PreCommitConfig> {
+    load_config_with(root, |p| fs::read_to_string(p))
+}
+
+/// The main config merged with `LOCAL_CONFIG_PATH`, or whichever of the two exists.
+fn load_config_with(root: &Path, read: impl Fn(&Path) -> io::Result<String>) -> Option<PreCommitConfig> {
+    let main = find_config_file(root).and_then(|path| parse_config_file(&path, &read));
+    let local_path = root.join(LOCAL_CONFIG_PATH);
+    let local = if local_path.is_file() {
+        parse_config_file(&local_path, &read)
+    } else {
+        None
+    };
+    match (main, local) {
+        (Some(mut main), Some(local)) => {
+            main.merge_local(local);
+            Some(main)
+        }
+        (main, local) => main.or(local),
+    }
+}
 
-        let mut root_mapping = Mapping::new();
-        root_mapping.insert(str_val(hook_name), Value::Mapping(hook_mapping));
+fn parse_config_file(path: &Path, read: impl Fn(&Path) -> io::Result<String>) -> Option<PreCommitConfig> {
+    let content = read(path).ok()?;
+    let mut config: PreCommitConfig = match serde_yaml::from_str(&normalize_newlines(&content)) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("ignoring {}: {e}", path.display());
+            return None;
+        }
+    };
+    normalize_stages(&mut config.default_stages);
+    for hook in config.repos.iter_mut().flat_map(|r| &mut r.hooks) {
+        normalize_stages(&mut hook.stages);
+    }
+    if let Some(exclude) = config.exclude.take() {
+        for hook in config.repos.iter_mut().flat_map(|r| &mut r.hooks) {
+            hook.exclude = Some(combine_excludes(&exclude, hook.exclude.as_deref()));
+        }
+    }
+    Some(config)
+}
 
-        Some(Value::Mapping(root_mapping))
+/// pre-commit's top-level `exclude` applies to every hook in its file, but
+/// lefthook has no top-level `exclude`, so it is folded into each hook's own
+/// pattern: a file is skipped if either regex matches.
+fn combine_excludes(top: &str, hook: Option<&str>) -> String {
+    match hook {
+        Some(hook) => format!("(?:{top})|(?:{hook})"),
+        None => top.to_string(),
     }
 }
 
@@ -63,8 +245,45 @@ impl Adapter for PreCommitAdapter {
 struct PreCommitConfig {
     #[serde(default)]
     repos: Vec<Repo>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     default_stages: Vec<String>,
+    #[serde(default)]
+    fail_fast: bool,
+    /// Regex of files no hook in this file sees.
+    #[serde(default)]
+    exclude: Option<String>,
+}
+
+impl PreCommitConfig {
+    /// Add the repos of a local config. Its local hooks replace ours with the
+    /// same id, and its hooks without `stages` keep its own `default_stages`
+    /// rather than picking up ours.
+    fn merge_local(&mut self, mut local: PreCommitConfig) {
+        let ids: Vec<String> = local
+            .repos
+            .iter()
+            .filter(|r| r.repo == "local")
+            .flat_map(|r| &r.hooks)
+            .map(|h| h.id.clone())
+            .collect();
+        for repo in self.repos.iter_mut().filter(|r| r.repo == "local") {
+            repo.hooks.retain(|h| !ids.contains(&h.id));
+        }
+        if !local.default_stages.is_empty() || !self.default_stages.is_empty() {
+            let stages: Vec<String> = if local.default_stages.is_empty() {
+                GIT_HOOKS.iter().map(|h| h.to_string()).collect()
+            } else {
+                local.default_stages.clone()
+            };
+            for hook in local.repos.iter_mut().flat_map(|r| &mut r.hooks) {
+                if hook.stages.is_empty() {
+                    hook.stages = stages.clone();
+                }
+            }
+        }
+        self.fail_fast |= local.fail_fast;
+        self.repos.extend(local.repos);
+    }
 }
 
 #[derive(Deserialize)]
@@ -79,9 +298,9 @@ struct Hook {
     id: String,
     #[serde(default)]
     entry: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     args: Vec<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     stages: Vec<String>,
     #[serde(default)]
     files: Option<String>,
@@ -89,49 +308,139 @@ struct Hook {
     exclude: Option<String>,
     #[serde(default = "default_true")]
     pass_filenames: bool,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     types: Vec<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     types_or: Vec<String>,
+    #[serde(default, deserialize_with = "string_list")]
+    exclude_types: Vec<String>,
+    #[serde(default)]
+    always_run: bool,
+    #[serde(default)]
+    fail_fast: bool,
+    #[serde(default)]
+    language: Option<String>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// A list of scalars as strings, so `args: [--max-line-length, 120]` keeps the
+/// number, and an empty `args:` (null) is an empty list.
+fn string_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let items = match Value::deserialize(deserializer)? {
+        Value::Null => return Ok(Vec::new()),
+        Value::Sequence(items) => items,
+        other => vec![other],
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(s) => Ok(s),
+            Value::Number(n) => Ok(n.to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            other => Err(serde::de::Error::custom(format!(
+                "expected a string, found {}",
+                crate::validate::type_name(&other)
+            ))),
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Translation helpers
 // ---------------------------------------------------------------------------
 
+/// Rewrite pre-commit's legacy stage names to the git hook names they stand
+/// for. pre-commit 3.2 introduced the git hook names but still accepts the
+/// old ones, which never collide with a git hook, so they are always mapped.
+fn normalize_stages(stages: &mut [String]) {
+    for stage in stages {
+        let hook = match stage.as_str() {
+            "commit" => "pre-commit",
+            "merge-commit" => "pre-merge-commit",
+            "push" => "pre-push",
+            _ => continue,
+        };
+        *stage = hook.to_string();
+    }
+}
+
+/// pre-commit's stage for hooks that only run when explicitly invoked. No git
+/// hook triggers it, so its hooks become a `manual` lefthook hook of their own,
+/// with every command tagged `manual`.
+const MANUAL_STAGE: &str = "manual";
+
 /// Check whether a hook should run for the given git hook stage.
 ///
 /// Falls back to `default_stages` when the hook has no explicit `stages`.
-/// Empty stages (at both levels) means the hook runs for all stages.
+/// Empty stages (at both levels) means the hook runs for all git hook stages;
+/// only hooks that list `manual` run for the manual stage.
 fn hook_matches_stage(hook: &Hook, default_stages: &[String], hook_name: &str) -> bool {
     let stages = if hook.stages.is_empty() {
         default_stages
     } else {
         &hook.stages
     };
-    stages.is_empty() || stages.iter().any(|s| s == hook_name)
+    (stages.is_empty() && hook_name != MANUAL_STAGE) || stages.iter().any(|s| s == hook_name)
 }
 
-/// Translate a single pre-commit hook into a lefthook command mapping.
+/// Translate a single pre-commit hook into a lefthook command mapping for the
+/// git hook `hook_name`.
 ///
 /// Returns `None` if the hook has no `entry` (which happens for remote-repo
 /// hooks that only specify `id`).
-fn translate_hook(hook: &Hook) -> Option<Mapping> {
+fn translate_hook(hook: &Hook, hook_name: &str) -> Option<Mapping> {
     let entry = hook.entry.as_deref()?;
+    let module_entry;
+    let entry = match hook.language.as_deref() {
+        Some("python") if is_python_module(entry) => {
+            module_entry = format!("python -m {entry}");
+            module_entry.as_str()
+        }
+        Some("script") if is_bare_relative_path(entry) => {
+            module_entry = format!("./{entry}");
+            module_entry.as_str()
+        }
+        _ => entry,
+    };
 
-    let mut run_parts = Vec::with_capacity(1 + hook.args.len() + 1);
-    run_parts.push(entry.to_string());
+    let has_marker = entry.contains(FILES_MARKER) || hook.args.iter().any(|a| a.contains(FILES_MARKER));
+    let places_files = has_marker || FILE_PLACEHOLDERS.iter().any(|p| entry.contains(p));
+    // lefthook skips a command whose file placeholder comes out empty, which
+    // would keep an `always_run` hook from running when nothing matches, so
+    // those run without files.
+    let passes_files = hook.pass_filenames && !hook.always_run;
+    let placeholder = files_placeholder(hook_name).filter(|_| passes_files && !places_files);
+
+    let mut run_parts = Vec::with_capacity(1 + hook.args.len() + 2);
+    match shell_script_entry(entry).filter(|_| placeholder.is_some()) {
+        // Files appended after `sh -c '...'` would land in $0.. and be ignored,
+        // so forward them into the script via "$@".
+        Some((shell, script)) => {
+            run_parts.push(format!("{shell} -c '{script} \"$@\"'"));
+            run_parts.push("--".to_string());
+        }
+        None => run_parts.push(entry.to_string()),
+    }
     run_parts.extend(hook.args.iter().cloned());
-    if hook.pass_filenames {
-        run_parts.push("{staged_files}".to_string());
+    if let Some(placeholder) = placeholder {
+        run_parts.push(placeholder.to_string());
+    }
+
+    let mut run = run_parts.join(" ");
+    if has_marker {
+        run = place_files(&run, files_placeholder(hook_name).filter(|_| passes_files));
     }
 
     let mut cmd = Mapping::new();
-    cmd.insert(str_val("run"), str_val(&run_parts.join(" ")));
+    cmd.insert(str_val("run"), str_val(&run));
+
+    // `always_run` hooks must not be gated by lefthook's file filters
+    if hook.always_run {
+        return Some(cmd);
+    }
 
     if let Some(ref files) = hook.files {
         cmd.insert(str_val("files"), str_val(files));
@@ -139,18 +448,87 @@ fn translate_hook(hook: &Hook) -> Option<Mapping> {
     if let Some(ref exclude) = hook.exclude {
         cmd.insert(str_val("exclude"), str_val(exclude));
     }
-    if let Some(glob) = types_to_glob(&hook.types, &hook.types_or) {
+    if let Some(glob) = types_to_glob(&hook.types, &hook.types_or, &hook.exclude_types) {
         cmd.insert(str_val("glob"), str_val(&glob));
     }
 
     Some(cmd)
 }
 
+/// Heuristic for `language: python` entries naming a module (e.g.
+/// `mypkg.hooks.check`) rather than a console script or file path: a single
+/// dotted identifier with no path separator or `.py` suffix.
+///
+/// lhm can't recreate pre-commit's managed venv, so the module (and any console
+/// scripts) must already be importable/on `PATH` in the user's environment.
+fn is_python_module(entry: &str) -> bool {
+    let word = entry.split_whitespace().next().unwrap_or("");
+    word.contains('.')
+        && !word.ends_with(".py")
+        && word
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+/// Whether the first word of a `language: script` entry is a repo-relative
+/// path without a leading `./`, which the shell would look up on `PATH` instead.
+fn is_bare_relative_path(entry: &str) -> bool {
+    let word = entry.split_whitespace().next().unwrap_or("");
+    !word.is_empty() && !word.starts_with('/') && !word.starts_with("./") && !word.starts_with("../")
+}
+
+/// The lefthook placeholder for the files a `hook_name` hook should check:
+/// the pushed files for `pre-push`, every file for `manual` (like
+/// `pre-commit run --all-files`), nothing for the commit message hooks
+/// (which get the message file instead), staged files otherwise.
+fn files_placeholder(hook_name: &str) -> Option<&'static str> {
+    match hook_name {
+        "pre-push" => Some("{push_files}"),
+        MANUAL_STAGE => Some("{all_files}"),
+        "commit-msg" | "prepare-commit-msg" => None,
+        _ => Some("{staged_files}"),
+    }
+}
+
+/// Marker in `entry`/`args` for where the files go when they can't be last.
+const FILES_MARKER: &str = "{files}";
+
+/// Replace each `{files}` marker in `run` with `placeholder`, or drop it
+/// (with its leading space) when the hook passes no files.
+fn place_files(run: &str, placeholder: Option<&str>) -> String {
+    match placeholder {
+        Some(placeholder) => run.replace(FILES_MARKER, placeholder),
+        None => run.replace(&format!(" {FILES_MARKER}"), "").replace(FILES_MARKER, ""),
+    }
+}
+
+/// lefthook file placeholders; an entry using one already places its files.
+const FILE_PLACEHOLDERS: &[&str] = &["{staged_files}", "{all_files}", "{push_files}"];
+
+/// Split an entry of the form `<shell> -c '<script>'` into shell and script.
+///
+/// Returns `None` for any other entry, or when the script already forwards its
+/// arguments via `$@`/`$*`.
+fn shell_script_entry(entry: &str) -> Option<(&str, &str)> {
+    let (shell, rest) = entry.split_once(" -c ")?;
+    let shell_name = shell.rsplit('/').next().unwrap_or(shell);
+    if !["sh", "bash", "zsh"].contains(&shell_name) {
+        return None;
+    }
+    let script = rest.trim().strip_prefix('\'')?.strip_suffix('\'')?;
+    if script.contains('\'') || script.contains("$@") || script.contains("$*") {
+        return None;
+    }
+    Some((shell, script))
+}
+
 /// Map pre-commit `types` / `types_or` to a lefthook `glob` pattern.
 ///
 /// `types` uses AND logic (in practice usually a single file type).
-/// `types_or` uses OR logic. Both are combined into one glob.
-fn types_to_glob(types: &[String], types_or: &[String]) -> Option<String> {
+/// `types_or` uses OR logic. Both are combined into one glob, minus the
+/// extensions of `exclude_types`. A glob can only list extensions, so
+/// `exclude_types` narrows nothing when the other types don't map to any.
+fn types_to_glob(types: &[String], types_or: &[String], exclude_types: &[String]) -> Option<String> {
     let mut extensions: Vec<&str> = Vec::new();
 
     for ty in types {
@@ -164,6 +542,13 @@ fn types_to_glob(types: &[String], types_or: &[String]) -> Option<String> {
         }
     }
 
+    let excluded: Vec<&str> = exclude_types
+        .iter()
+        .filter_map(|ty| type_to_extensions(ty))
+        .flat_map(|ext| ext.split(','))
+        .collect();
+    extensions.retain(|ext| !excluded.contains(ext));
+
     extensions.sort_unstable();
     extensions.dedup();
 
@@ -227,6 +612,165 @@ mod tests {
         PreCommitAdapter
     }
 
+    /// A local hook with every optional field at its default.
+    fn base_hook() -> Hook {
+        Hook {
+            id: "x".into(),
+            entry: None,
+            args: vec![],
+            stages: vec![],
+            files: None,
+            exclude: None,
+            pass_filenames: true,
+            types: vec![],
+            types_or: vec![],
+            exclude_types: vec![],
+            always_run: false,
+            fail_fast: false,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_config_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            adapter().config_sources(dir.path()),
+            [
+                dir.path().join(".pre-commit-config.yaml"),
+                dir.path().join(".config/pre-commit-config.yaml"),
+                dir.path().join(".config/pre-commit/config.yaml"),
+                dir.path().join(".pre-commit-config.local.yaml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_config_merges_local_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+default_stages: [pre-commit]
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+      - id: lint
+        entry: lint
+"#,
+        );
+        fs::write(
+            dir.path().join(LOCAL_CONFIG_PATH),
+            r#"
+repos:
+  - repo: local
+    hooks:
+      - id: lint
+        entry: my-lint
+      - id: secrets
+        entry: scan-secrets
+        stages: [pre-push]
+      - id: notify
+        entry: notify
+"#,
+        )
+        .unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let commands = config["pre-commit"]["commands"].as_mapping().unwrap();
+        let runs: Vec<&str> = commands.values().map(|c| c["run"].as_str().unwrap()).collect();
+        assert_eq!(
+            runs,
+            ["fmt {staged_files}", "my-lint {staged_files}", "notify {staged_files}"]
+        );
+        let config = adapter().generate_config(dir.path(), "pre-push").unwrap();
+        let commands = config["pre-push"]["commands"].as_mapping().unwrap();
+        let names: Vec<&str> = commands.keys().filter_map(Value::as_str).collect();
+        assert_eq!(
+            names,
+            ["lint", "secrets", "notify"],
+            "main default_stages don't apply to local-config hooks"
+        );
+    }
+
+    #[test]
+    fn test_local_config_alone_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!adapter().detect(dir.path()));
+        fs::write(
+            dir.path().join(LOCAL_CONFIG_PATH),
+            "repos:\n  - repo: local\n    hooks:\n      - id: fmt\n        entry: fmt\n",
+        )
+        .unwrap();
+        assert!(adapter().detect(dir.path()));
+        assert!(adapter().generate_config(dir.path(), "pre-commit").is_some());
+    }
+
+    #[test]
+    fn test_load_config_real_world() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"ci:
+  autofix_commit_msg: "[pre-commit.ci] auto fixes"
+  autoupdate_schedule: quarterly
+  skip: [mypy-local]
+default_language_version:
+  python: python3.11
+default_install_hook_types: [pre-commit, pre-push]
+minimum_pre_commit_version: "3.2.0"
+exclude: ^vendor/
+repos:
+  - repo: https://github.com/pre-commit/pre-commit-hooks
+    rev: v4.6.0
+    hooks:
+      - id: trailing-whitespace
+      - id: check-yaml
+        args: [--allow-multiple-documents]
+  - repo: meta
+    hooks:
+      - id: check-hooks-apply
+  - repo: local
+    hooks:
+      - id: flake8
+        name: flake8
+        alias: lint
+        entry: flake8
+        language: python
+        additional_dependencies: [flake8-bugbear==24.2.6]
+        args: [--max-line-length, 120, --exit-zero, false]
+        types: [python]
+        require_serial: true
+        verbose: true
+        log_file: flake8.log
+      - id: mypy-local
+        name: mypy
+        description: type check
+        entry: mypy
+        language: system
+        args:
+        stages: [pre-push]
+        types_or: [python, pyi]
+        minimum_pre_commit_version: "2.9.2"
+"#,
+        );
+        let config = load_config(dir.path()).expect("config parses");
+        let hooks = &config.repos[2].hooks;
+        assert_eq!(hooks[0].args, ["--max-line-length", "120", "--exit-zero", "false"]);
+        assert!(hooks[1].args.is_empty());
+
+        let out = serde_yaml::to_string(&adapter().generate_config(dir.path(), "pre-commit").unwrap()).unwrap();
+        assert!(
+            out.contains("run: flake8 --max-line-length 120 --exit-zero false {staged_files}"),
+            "{out}"
+        );
+        assert!(!out.contains("mypy"), "{out}");
+        let out = serde_yaml::to_string(&adapter().generate_config(dir.path(), "pre-push").unwrap()).unwrap();
+        assert!(out.contains("run: mypy {push_files}"), "{out}");
+    }
+
     fn write_config(dir: &Path, content: &str) {
         fs::write(dir.join(".pre-commit-config.yaml"), content).unwrap();
     }
@@ -253,20 +797,53 @@ mod tests {
         assert!(!adapter().detect(dir.path()));
     }
 
+    #[test]
+    fn test_detect_dot_config_locations() {
+        for rel in [".config/pre-commit-config.yaml", ".config/pre-commit/config.yaml"] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "repos: []\n").unwrap();
+            assert!(adapter().detect(dir.path()), "detects {rel}");
+            assert_eq!(find_config_file(dir.path()), Some(path));
+        }
+    }
+
+    #[test]
+    fn test_find_config_file_prefers_root() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "repos: []\n");
+        fs::create_dir_all(dir.path().join(".config")).unwrap();
+        fs::write(dir.path().join(".config/pre-commit-config.yaml"), "repos: []\n").unwrap();
+        assert_eq!(
+            find_config_file(dir.path()),
+            Some(dir.path().join(".pre-commit-config.yaml"))
+        );
+    }
+
+    #[test]
+    fn test_generate_config_from_dot_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".config/pre-commit")).unwrap();
+        fs::write(
+            dir.path().join(".config/pre-commit/config.yaml"),
+            "repos:\n  - repo: local\n    hooks:\n      - id: fmt\n        entry: fmt\n",
+        )
+        .unwrap();
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let out = serde_yaml::to_string(&config).unwrap();
+        assert!(out.contains("fmt {staged_files}"), "fmt cmd: {out}");
+    }
+
     // -- stage matching --
 
     #[test]
     fn test_hook_matches_stage_explicit() {
         let hook = Hook {
             id: "x".into(),
-            entry: None,
-            args: vec![],
             stages: vec!["pre-commit".into()],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
         assert!(hook_matches_stage(&hook, &[], "pre-commit"));
         assert!(!hook_matches_stage(&hook, &[], "pre-push"));
@@ -276,14 +853,7 @@ mod tests {
     fn test_hook_matches_stage_default_stages() {
         let hook = Hook {
             id: "x".into(),
-            entry: None,
-            args: vec![],
-            stages: vec![],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
         let defaults = vec!["pre-push".to_string()];
         assert!(hook_matches_stage(&hook, &defaults, "pre-push"));
@@ -294,14 +864,7 @@ mod tests {
     fn test_hook_matches_stage_no_stages_means_all() {
         let hook = Hook {
             id: "x".into(),
-            entry: None,
-            args: vec![],
-            stages: vec![],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
         assert!(hook_matches_stage(&hook, &[], "pre-commit"));
         assert!(hook_matches_stage(&hook, &[], "pre-push"));
@@ -312,12 +875,12 @@ mod tests {
 
     #[test]
     fn test_types_to_glob_single() {
-        assert_eq!(types_to_glob(&["python".into()], &[]), Some("*.py".into()));
+        assert_eq!(types_to_glob(&["python".into()], &[], &[]), Some("*.py".into()));
     }
 
     #[test]
     fn test_types_to_glob_or() {
-        let glob = types_to_glob(&[], &["javascript".into(), "typescript".into()]).unwrap();
+        let glob = types_to_glob(&[], &["javascript".into(), "typescript".into()], &[]).unwrap();
         assert!(glob.contains("js"));
         assert!(glob.contains("ts"));
         assert!(glob.starts_with("*.{"));
@@ -325,20 +888,50 @@ mod tests {
 
     #[test]
     fn test_types_to_glob_yaml_multi_ext() {
-        let glob = types_to_glob(&["yaml".into()], &[]).unwrap();
+        let glob = types_to_glob(&["yaml".into()], &[], &[]).unwrap();
         assert!(glob.contains("yml"));
         assert!(glob.contains("yaml"));
     }
 
     #[test]
     fn test_types_to_glob_skips_generic_types() {
-        assert_eq!(types_to_glob(&["file".into()], &[]), None);
-        assert_eq!(types_to_glob(&["text".into()], &[]), None);
+        assert_eq!(types_to_glob(&["file".into()], &[], &[]), None);
+        assert_eq!(types_to_glob(&["text".into()], &[], &[]), None);
+    }
+
+    #[test]
+    fn test_types_to_glob_exclude_types() {
+        let types_or = ["python".into(), "javascript".into(), "yaml".into()];
+        assert_eq!(
+            types_to_glob(&[], &types_or, &["javascript".into()]),
+            Some("*.{py,yaml,yml}".into())
+        );
+        assert_eq!(
+            types_to_glob(&[], &types_or, &["javascript".into(), "yaml".into()]),
+            Some("*.py".into())
+        );
+        // Everything excluded: no glob rather than one matching nothing
+        assert_eq!(types_to_glob(&["python".into()], &[], &["python".into()]), None);
+        // `text` has no extensions to subtract from
+        assert_eq!(types_to_glob(&["text".into()], &[], &["python".into()]), None);
+    }
+
+    #[test]
+    fn test_translate_hook_exclude_types() {
+        let hook = Hook {
+            id: "prettier".into(),
+            entry: Some("prettier --check".into()),
+            types_or: vec!["javascript".into(), "typescript".into(), "markdown".into()],
+            exclude_types: vec!["markdown".into()],
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(cmd.get("glob").unwrap().as_str(), Some("*.{js,ts}"));
     }
 
     #[test]
     fn test_types_to_glob_mixed_types_and_types_or() {
-        let glob = types_to_glob(&["python".into()], &["ruby".into()]).unwrap();
+        let glob = types_to_glob(&["python".into()], &["ruby".into()], &[]).unwrap();
         assert!(glob.contains("py"));
         assert!(glob.contains("rb"));
     }
@@ -350,15 +943,10 @@ mod tests {
         let hook = Hook {
             id: "black".into(),
             entry: Some("black".into()),
-            args: vec![],
-            stages: vec![],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
             types: vec!["python".into()],
-            types_or: vec![],
+            ..base_hook()
         };
-        let cmd = translate_hook(&hook).unwrap();
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
         let run = cmd.get("run").unwrap().as_str().unwrap();
         assert_eq!(run, "black {staged_files}");
         assert_eq!(cmd.get("glob").unwrap().as_str().unwrap(), "*.py");
@@ -370,32 +958,41 @@ mod tests {
             id: "flake8".into(),
             entry: Some("flake8".into()),
             args: vec!["--max-line-length=100".into()],
-            stages: vec![],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
-        let cmd = translate_hook(&hook).unwrap();
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
         let run = cmd.get("run").unwrap().as_str().unwrap();
         assert_eq!(run, "flake8 --max-line-length=100 {staged_files}");
     }
 
     #[test]
-    fn test_translate_hook_no_pass_filenames() {
+    fn test_translate_hook_placeholder_per_hook() {
         let hook = Hook {
             id: "check".into(),
-            entry: Some("./check.sh".into()),
-            args: vec![],
-            stages: vec![],
-            files: None,
-            exclude: None,
+            entry: Some("check".into()),
+            ..base_hook()
+        };
+        let run = |hook_name| {
+            translate_hook(&hook, hook_name).unwrap()["run"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(run("pre-commit"), "check {staged_files}");
+        assert_eq!(run("pre-push"), "check {push_files}");
+        assert_eq!(run("commit-msg"), "check");
+        assert_eq!(run("prepare-commit-msg"), "check");
+    }
+
+    #[test]
+    fn test_translate_hook_no_pass_filenames() {
+        let hook = Hook {
+            id: "check".into(),
+            entry: Some("./check.sh".into()),
             pass_filenames: false,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
-        let cmd = translate_hook(&hook).unwrap();
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
         let run = cmd.get("run").unwrap().as_str().unwrap();
         assert_eq!(run, "./check.sh");
     }
@@ -405,15 +1002,11 @@ mod tests {
         let hook = Hook {
             id: "lint".into(),
             entry: Some("lint".into()),
-            args: vec![],
-            stages: vec![],
             files: Some(r"\.py$".into()),
             exclude: Some(r"^tests/".into()),
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
         };
-        let cmd = translate_hook(&hook).unwrap();
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
         assert_eq!(cmd.get("files").unwrap().as_str().unwrap(), r"\.py$");
         assert_eq!(cmd.get("exclude").unwrap().as_str().unwrap(), r"^tests/");
     }
@@ -422,16 +1015,352 @@ mod tests {
     fn test_translate_hook_no_entry_returns_none() {
         let hook = Hook {
             id: "remote-only".into(),
-            entry: None,
-            args: vec![],
-            stages: vec![],
-            files: None,
-            exclude: None,
-            pass_filenames: true,
-            types: vec![],
-            types_or: vec![],
+            ..base_hook()
+        };
+        assert!(translate_hook(&hook, "pre-commit").is_none());
+    }
+
+    #[test]
+    fn test_translate_hook_shell_script_entry_forwards_files() {
+        let hook = Hook {
+            id: "prettier".into(),
+            entry: Some("bash -c 'prettier --write'".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get("run").unwrap().as_str().unwrap(),
+            r#"bash -c 'prettier --write "$@"' -- {staged_files}"#
+        );
+    }
+
+    #[test]
+    fn test_translate_hook_shell_script_entry_with_args() {
+        let hook = Hook {
+            id: "prettier".into(),
+            entry: Some("/bin/sh -c 'prettier'".into()),
+            args: vec!["--write".into()],
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get("run").unwrap().as_str().unwrap(),
+            r#"/bin/sh -c 'prettier "$@"' -- --write {staged_files}"#
+        );
+    }
+
+    #[test]
+    fn test_translate_hook_shell_script_entry_without_filenames() {
+        let hook = Hook {
+            id: "check".into(),
+            entry: Some("bash -c 'make check'".into()),
+            pass_filenames: false,
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(cmd.get("run").unwrap().as_str().unwrap(), "bash -c 'make check'");
+    }
+
+    #[test]
+    fn test_translate_hook_shell_script_already_forwarding_args() {
+        let hook = Hook {
+            id: "prettier".into(),
+            entry: Some(r#"bash -c 'prettier --write "$@"' --"#.into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get("run").unwrap().as_str().unwrap(),
+            r#"bash -c 'prettier --write "$@"' -- {staged_files}"#
+        );
+    }
+
+    #[test]
+    fn test_translate_hook_entry_with_placeholder_not_appended() {
+        let hook = Hook {
+            id: "lint".into(),
+            entry: Some("lint {staged_files} --strict".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get("run").unwrap().as_str().unwrap(),
+            "lint {staged_files} --strict"
+        );
+    }
+
+    #[test]
+    fn test_is_python_module() {
+        assert!(is_python_module("mypkg.hooks.check"));
+        assert!(is_python_module("tools.lint --strict"));
+        assert!(!is_python_module("black"));
+        assert!(!is_python_module("scripts/check.py"));
+        assert!(!is_python_module("check.py"));
+        assert!(!is_python_module("./bin/lint"));
+    }
+
+    #[test]
+    fn test_translate_hook_python_module_prefixed() {
+        let hook = Hook {
+            entry: Some("mypkg.hooks.check".into()),
+            language: Some("python".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get(str_val("run")).unwrap().as_str(),
+            Some("python -m mypkg.hooks.check {staged_files}")
+        );
+    }
+
+    #[test]
+    fn test_translate_hook_python_script_unchanged() {
+        let hook = Hook {
+            entry: Some("black".into()),
+            language: Some("python".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(cmd.get(str_val("run")).unwrap().as_str(), Some("black {staged_files}"));
+
+        let system = Hook {
+            entry: Some("mypkg.hooks.check".into()),
+            language: Some("system".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&system, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get(str_val("run")).unwrap().as_str(),
+            Some("mypkg.hooks.check {staged_files}")
+        );
+    }
+
+    #[test]
+    fn test_translate_hook_files_marker_placement() {
+        let run = |entry: &str, args: &[&str], hook_name: &str, pass_filenames: bool| {
+            let hook = Hook {
+                entry: Some(entry.into()),
+                args: args.iter().map(|a| a.to_string()).collect(),
+                pass_filenames,
+                ..base_hook()
+            };
+            let cmd = translate_hook(&hook, hook_name).unwrap();
+            cmd.get(str_val("run")).unwrap().as_str().unwrap().to_string()
         };
-        assert!(translate_hook(&hook).is_none());
+        assert_eq!(
+            run("tool check {files} --report", &[], "pre-commit", true),
+            "tool check {staged_files} --report"
+        );
+        assert_eq!(
+            run("tool", &["--in", "{files}", "--out", "report.txt"], "pre-push", true),
+            "tool --in {push_files} --out report.txt"
+        );
+        assert_eq!(run("tool {files} --all", &[], "pre-commit", false), "tool --all");
+        assert_eq!(run("tool", &["{files}", "--end"], "commit-msg", true), "tool --end");
+        // Without a marker the files are still appended
+        assert_eq!(run("tool", &["--end"], "pre-commit", true), "tool --end {staged_files}");
+    }
+
+    #[test]
+    fn test_translate_hook_script_language_prefixes_path() {
+        let hook = Hook {
+            entry: Some("scripts/check.sh --fast".into()),
+            language: Some("script".into()),
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(
+            cmd.get(str_val("run")).unwrap().as_str(),
+            Some("./scripts/check.sh --fast {staged_files}")
+        );
+
+        for entry in ["./check.sh", "../tools/check.sh", "/opt/check.sh"] {
+            let hook = Hook {
+                entry: Some(entry.into()),
+                language: Some("script".into()),
+                ..base_hook()
+            };
+            let cmd = translate_hook(&hook, "pre-commit").unwrap();
+            assert_eq!(
+                cmd.get(str_val("run")).unwrap().as_str(),
+                Some(format!("{entry} {{staged_files}}").as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_translate_hook_always_run_skips_gating() {
+        let hook = Hook {
+            id: "check".into(),
+            entry: Some("./check.sh".into()),
+            files: Some(r"\.py$".into()),
+            exclude: Some(r"^tests/".into()),
+            pass_filenames: false,
+            types: vec!["python".into()],
+            always_run: true,
+            ..base_hook()
+        };
+        let cmd = translate_hook(&hook, "pre-commit").unwrap();
+        assert_eq!(cmd.get("run").unwrap().as_str().unwrap(), "./check.sh");
+        assert!(cmd.get("glob").is_none());
+        assert!(cmd.get("files").is_none());
+        assert!(cmd.get("exclude").is_none());
+    }
+
+    #[test]
+    fn test_translate_hook_always_run_passes_no_files() {
+        let hook = Hook {
+            id: "check".into(),
+            entry: Some("./check.sh".into()),
+            args: vec!["--strict".into()],
+            always_run: true,
+            ..base_hook()
+        };
+        for stage in ["pre-commit", "pre-push"] {
+            let cmd = translate_hook(&hook, stage).unwrap();
+            assert_eq!(cmd["run"].as_str(), Some("./check.sh --strict"), "{stage}");
+        }
+
+        let marked = Hook {
+            args: vec!["{files}".into(), "--report".into()],
+            ..hook
+        };
+        let cmd = translate_hook(&marked, "pre-commit").unwrap();
+        assert_eq!(cmd["run"].as_str(), Some("./check.sh --report"));
+    }
+
+    #[test]
+    fn test_generate_config_always_run() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+repos:
+  - repo: local
+    hooks:
+      - id: check
+        entry: ./check.sh
+        language: system
+        pass_filenames: false
+        always_run: true
+        types: [python]
+"#,
+        );
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let out = serde_yaml::to_string(&config).unwrap();
+        assert!(out.contains("run: ./check.sh"), "run: {out}");
+        assert!(!out.contains("glob"), "no glob: {out}");
+    }
+
+    #[test]
+    fn test_generate_config_fail_fast_sets_piped() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+fail_fast: true
+repos:
+  - repo: local
+    hooks:
+      - id: lint
+        entry: ./lint.sh
+        language: system
+"#,
+        );
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        assert_eq!(config["pre-commit"]["piped"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_top_level_exclude_applies_to_every_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+exclude: ^vendor/
+repos:
+  - repo: local
+    hooks:
+      - id: lint
+        entry: ./lint.sh
+        language: system
+      - id: fmt
+        entry: ./fmt.sh
+        language: system
+        exclude: \.lock$
+      - id: audit
+        entry: ./audit.sh
+        language: system
+        always_run: true
+"#,
+        );
+
+        let config = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let commands = &config["pre-commit"]["commands"];
+        assert_eq!(commands["lint"]["exclude"].as_str(), Some("^vendor/"));
+        assert_eq!(commands["fmt"]["exclude"].as_str(), Some(r"(?:^vendor/)|(?:\.lock$)"));
+        assert!(commands["audit"].get("exclude").is_none());
+        // lefthook has no top-level `exclude`, so none is emitted
+        assert!(config.get("exclude").is_none());
+    }
+
+    #[test]
+    fn test_generate_config_hook_fail_fast_sets_piped() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+repos:
+  - repo: local
+    hooks:
+      - id: lint
+        entry: ./lint.sh
+        language: system
+        fail_fast: true
+        stages: [pre-push]
+      - id: fmt
+        entry: ./fmt.sh
+        language: system
+        stages: [pre-commit]
+"#,
+        );
+
+        let push = adapter().generate_config(dir.path(), "pre-push").unwrap();
+        assert_eq!(push["pre-push"]["piped"], Value::Bool(true));
+        let commit = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        assert!(commit["pre-commit"].get("piped").is_none());
+    }
+
+    #[test]
+    fn test_prepare_reads_config_once() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+repos:
+  - repo: local
+    hooks:
+      - id: lint
+        entry: ./lint.sh
+        language: system
+        stages: [pre-commit, pre-push]
+"#,
+        );
+
+        let reads = std::cell::Cell::new(0);
+        let prepared = prepare_with(dir.path(), |p| {
+            reads.set(reads.get() + 1);
+            fs::read_to_string(p)
+        });
+        let hooks = prepared.supported_hooks();
+        assert_eq!(hooks, ["pre-commit", "pre-push"]);
+        for hook in GIT_HOOKS {
+            prepared.generate_config(hook);
+        }
+        assert_eq!(reads.get(), 1);
+        assert!(prepared.generate_config("pre-push").is_some());
     }
 
     // -- full adapter integration --
@@ -608,6 +1537,181 @@ repos:
         assert!(out.contains("tsx"), "glob has tsx: {out}");
     }
 
+    #[test]
+    fn test_manual_stage_becomes_tagged_command() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+      - id: slow-lint
+        entry: slow-lint
+        stages: [manual]
+"#,
+        );
+        assert_eq!(
+            adapter().supported_hooks(dir.path()),
+            [GIT_HOOKS.to_vec(), vec!["manual"]].concat()
+        );
+
+        let pre_commit = adapter().generate_config(dir.path(), "pre-commit").unwrap();
+        let commands = &pre_commit["pre-commit"]["commands"];
+        assert!(commands.get("slow-lint").is_none(), "manual-only hook runs on commit");
+
+        let manual = adapter().generate_config(dir.path(), "manual").unwrap();
+        let commands = manual["manual"]["commands"].as_mapping().unwrap();
+        assert_eq!(commands.len(), 1, "hooks without stages aren't manual: {commands:?}");
+        let lint = &commands["slow-lint"];
+        assert_eq!(lint["run"].as_str(), Some("slow-lint {all_files}"));
+        assert_eq!(lint["tags"], Value::Sequence(vec![str_val("manual")]));
+    }
+
+    #[test]
+    fn test_generate_all_matches_per_hook_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+default_stages: [pre-commit, pre-push]
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+        types: [rust]
+      - id: test
+        entry: cargo test
+        pass_filenames: false
+        stages: [pre-push]
+        fail_fast: true
+      - id: msg
+        entry: check-msg
+        stages: [commit-msg]
+      - id: audit
+        entry: audit
+        stages: [manual]
+  - repo: https://github.com/pre-commit/pre-commit-hooks
+    rev: v4.0.0
+    hooks:
+      - id: check-yaml
+"#,
+        );
+        let root = dir.path();
+        let per_hook = adapter()
+            .supported_hooks(root)
+            .iter()
+            .filter_map(|hook| adapter().generate_config(root, hook))
+            .reduce(crate::merge::merge_configs)
+            .unwrap();
+        let all = adapter().generate_all(root).unwrap();
+        assert_eq!(
+            serde_yaml::to_string(&all).unwrap(),
+            serde_yaml::to_string(&per_hook).unwrap()
+        );
+        assert_eq!(all["pre-push"]["piped"], Value::Bool(true));
+        assert!(all["pre-commit"].get("piped").is_none());
+
+        let empty = tempfile::tempdir().unwrap();
+        write_config(empty.path(), "repos: []\n");
+        assert!(adapter().generate_all(empty.path()).is_none());
+    }
+
+    #[test]
+    fn test_supported_hooks_from_stages() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+default_stages: [pre-commit]
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+      - id: test
+        entry: test
+        stages: [pre-push]
+  - repo: https://github.com/pre-commit/pre-commit-hooks
+    rev: v4.0.0
+    hooks:
+      - id: check-yaml
+        stages: [commit-msg]
+"#,
+        );
+        assert_eq!(adapter().supported_hooks(dir.path()), ["pre-commit", "pre-push"]);
+    }
+
+    #[test]
+    fn test_legacy_stage_names() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            r#"
+minimum_pre_commit_version: 2.20.0
+default_stages: [commit]
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+      - id: test
+        entry: test
+        stages: [push, merge-commit]
+"#,
+        );
+        assert_eq!(
+            adapter().supported_hooks(dir.path()),
+            ["pre-commit", "pre-merge-commit", "pre-push"]
+        );
+    }
+
+    #[test]
+    fn test_legacy_and_new_stage_names_mixed() {
+        let config = r#"
+minimum_pre_commit_version: '3.0'
+default_stages: [pre-commit]
+repos:
+  - repo: local
+    hooks:
+      - id: fmt
+        entry: fmt
+      - id: test
+        entry: test
+        stages: [pre-push, pre-merge-commit]
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), config);
+        assert_eq!(
+            adapter().supported_hooks(dir.path()),
+            ["pre-commit", "pre-merge-commit", "pre-push"]
+        );
+
+        // pre-commit 3.x still accepts `push`, whatever the declared minimum
+        write_config(dir.path(), &config.replace("[pre-push, pre-merge-commit]", "[push]"));
+        assert_eq!(adapter().supported_hooks(dir.path()), ["pre-commit", "pre-push"]);
+    }
+
+    #[test]
+    fn test_supported_hooks_unstaged_means_all() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            "repos:\n  - repo: local\n    hooks:\n      - id: fmt\n        entry: fmt\n",
+        );
+        assert_eq!(adapter().supported_hooks(dir.path()).len(), GIT_HOOKS.len());
+    }
+
+    #[test]
+    fn test_supported_hooks_empty_repos() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "repos: []\n");
+        assert!(adapter().supported_hooks(dir.path()).is_empty());
+    }
+
     #[test]
     fn test_generate_config_empty_repos() {
         let dir = tempfile::tempdir().unwrap();
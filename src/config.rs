@@ -1,8 +1,11 @@
-use log::{debug, info};
+use crate::merge::{MergeStrategy, merge_configs};
+use crate::validate::type_name;
+use log::{debug, info, warn};
+use serde::Deserialize;
 use serde_yaml::Value;
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
@@ -30,6 +33,34 @@ impl ConfigOverrides {
     }
 }
 
+/// Returns `true` if the env var is set to `1` or `true`.
+pub fn env_flag(key: &str) -> bool {
+    env::var(key).is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Env vars set by common CI systems, any of which marks a CI run.
+pub const CI_ENV_VARS: &[&str] = &[
+    "CI",
+    "GITHUB_ACTIONS",
+    "GITLAB_CI",
+    "BUILDKITE",
+    "CIRCLECI",
+    "JENKINS_URL",
+    "TF_BUILD",
+];
+
+/// Whether this is a CI run: `LHM_CI` decides when set (`1`/`true` or
+/// anything else), otherwise any non-empty `CI_ENV_VARS` entry other than
+/// `CI=false`. `var` looks up an env var.
+pub fn is_ci(var: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(v) = var("LHM_CI") {
+        return v == "1" || v == "true";
+    }
+    CI_ENV_VARS
+        .iter()
+        .any(|name| var(name).is_some_and(|v| !v.is_empty() && v != "false" && v != "0"))
+}
+
 pub const LEFTHOOK_EXTENSIONS: &[&str] = &["yml", "yaml", "json", "jsonc", "toml"];
 
 pub const DEFAULT_GLOBAL_CONFIG: &str = r#"# Global lefthook configuration
@@ -54,31 +85,67 @@ pre-commit:
       run: just fmt
       skip:
         - run: "! just --dry-run fmt"
+# Message hooks get git's arguments as {1}, {2}, {3}. For prepare-commit-msg
+# those are the message file (absolute path), the commit source (message,
+# template, merge, squash or commit; absent for a plain `git commit`) and, for
+# amends, the commit SHA. A script can leave merge messages alone by checking
+# its second argument:
+# prepare-commit-msg:
+#   commands:
+#     prefix:
+#       run: scripts/prefix-message {1} {2} {3}
 "#;
 
-/// Search for a lefthook config file in the given directory.
-/// Checks `lefthook.<ext>`, `.lefthook.<ext>`, and optionally `.config/lefthook.<ext>`.
-pub fn find_config(dir: &Path, check_dot_config: bool) -> Option<PathBuf> {
-    for ext in LEFTHOOK_EXTENSIONS {
-        let candidates = if check_dot_config {
-            vec![
-                dir.join(format!("lefthook.{ext}")),
-                dir.join(format!(".lefthook.{ext}")),
-                dir.join(format!(".config/lefthook.{ext}")),
-            ]
-        } else {
-            vec![
-                dir.join(format!("lefthook.{ext}")),
-                dir.join(format!(".lefthook.{ext}")),
-            ]
-        };
-        for candidate in candidates {
-            if candidate.is_file() {
-                return Some(candidate);
-            }
+/// Neutral template for users without `just`: output settings and empty hook
+/// stanzas to fill in.
+pub const MINIMAL_GLOBAL_CONFIG: &str = r#"# Global lefthook configuration
+output:
+  - success
+  - failure
+pre-commit:
+  commands: {}
+pre-push:
+  commands: {}
+"#;
+
+/// Which template `install` writes when there is no global config yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ConfigProfile {
+    /// `DEFAULT_GLOBAL_CONFIG`, with `just`-based fmt, lint and test commands.
+    #[default]
+    Full,
+    /// `MINIMAL_GLOBAL_CONFIG`.
+    Minimal,
+}
+
+impl ConfigProfile {
+    pub fn template(self) -> &'static str {
+        match self {
+            Self::Full => DEFAULT_GLOBAL_CONFIG,
+            Self::Minimal => MINIMAL_GLOBAL_CONFIG,
         }
     }
-    None
+}
+
+/// Search for a lefthook config file in the given directory.
+///
+/// Precedence is by name first, then by extension (in `LEFTHOOK_EXTENSIONS`
+/// order): every `lefthook.<ext>`, then every `.lefthook.<ext>`, then (if
+/// `check_dot_config`) every `.config/lefthook.<ext>`.
+pub fn find_config(dir: &Path, check_dot_config: bool) -> Option<PathBuf> {
+    let stems: &[&str] = if check_dot_config {
+        &["lefthook", ".lefthook", ".config/lefthook"]
+    } else {
+        &["lefthook", ".lefthook"]
+    };
+    stems
+        .iter()
+        .flat_map(|stem| {
+            LEFTHOOK_EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("{stem}.{ext}")))
+        })
+        .find(|candidate| candidate.is_file())
 }
 
 pub fn global_config(home: &Path, overrides: &ConfigOverrides) -> Option<PathBuf> {
@@ -97,22 +164,341 @@ pub fn repo_config(root: &Path, overrides: &ConfigOverrides) -> Option<PathBuf>
     find_config(root, true)
 }
 
-/// Write the default global config to `~/.lefthook.yaml` if no global config exists.
-pub fn install_default_global_config(home: &Path) -> Result<(), String> {
-    if find_config(home, false).is_some() {
+/// Top-level key holding lhm's own settings. It is read from the global config
+/// and stripped before the merged config reaches lefthook.
+pub const LHM_SETTINGS_KEY: &str = "lhm";
+
+/// Keys lhm reads from the `lhm` settings mapping.
+pub const LHM_SETTINGS: &[&str] = &[
+    "disable_adapters",
+    "include",
+    "jobs",
+    "merge_adapters",
+    "merge_strategy",
+    "nested_configs",
+    "output",
+];
+
+/// Adapter names disabled via `LHM_DISABLE_ADAPTERS` (comma-separated) or an
+/// `lhm.disable_adapters` list in the global config.
+pub fn disabled_adapters(global: Option<&Value>) -> Vec<String> {
+    let mut disabled: Vec<String> = env::var("LHM_DISABLE_ADAPTERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    if let Some(Value::Sequence(names)) = global
+        .and_then(|g| g.get(LHM_SETTINGS_KEY))
+        .and_then(|lhm| lhm.get("disable_adapters"))
+    {
+        disabled.extend(names.iter().filter_map(Value::as_str).map(String::from));
+    }
+    disabled
+}
+
+/// Whether the boolean setting `lhm.<key>` (e.g. `merge_adapters`) is on. The
+/// repo config's setting wins over the global one.
+pub fn lhm_flag(global: Option<&Value>, repo: Option<&Value>, key: &str) -> bool {
+    [repo, global]
+        .into_iter()
+        .flatten()
+        .find_map(|c| c.get(LHM_SETTINGS_KEY)?.get(key)?.as_bool())
+        .unwrap_or(false)
+}
+
+/// The `lhm.merge_strategy` setting, with the repo config's value winning
+/// over the global one. Defaults to `repo-wins`.
+pub fn merge_strategy(global: Option<&Value>, repo: Option<&Value>) -> Result<MergeStrategy, String> {
+    let setting = [repo, global]
+        .into_iter()
+        .flatten()
+        .find_map(|c| c.get(LHM_SETTINGS_KEY)?.get("merge_strategy"));
+    match setting {
+        None | Some(Value::Null) => Ok(MergeStrategy::default()),
+        Some(Value::String(name)) => MergeStrategy::parse(name),
+        Some(other) => Err(format!(
+            "`lhm.merge_strategy` must be a string, found {}",
+            type_name(other)
+        )),
+    }
+}
+
+/// Configs in the directories below `root` down to `cwd` (inclusive),
+/// outermost first, for `lhm.nested_configs`. Empty when `cwd` isn't inside `root`.
+pub fn nested_config_paths(root: &Path, cwd: &Path) -> Vec<PathBuf> {
+    let Ok(rel) = cwd.strip_prefix(root) else {
+        return Vec::new();
+    };
+    let mut dir = root.to_path_buf();
+    let mut paths = Vec::new();
+    for component in rel.components() {
+        dir.push(component);
+        paths.extend(find_config(&dir, true));
+    }
+    paths
+}
+
+/// Merge the nested configs between `root` and `cwd` onto the repo config,
+/// so the config closest to `cwd` wins.
+pub fn merge_nested_configs(repo: Option<Value>, root: &Path, cwd: &Path) -> Result<Option<Value>, String> {
+    let mut config = repo;
+    for path in nested_config_paths(root, cwd) {
+        debug!("merging nested config: {}", path.display());
+        let nested = read_yaml(&path)?;
+        config = Some(match config {
+            Some(c) => merge_configs(c, nested),
+            None => nested,
+        });
+    }
+    Ok(config)
+}
+
+/// Per-repo lhm settings file, read from the repo root.
+pub const REPO_SETTINGS_FILE: &str = ".lhm.yml";
+
+/// Adapter pinned by `adapter: <name>` in `<root>/.lhm.yml`, if any.
+pub fn pinned_adapter(root: &Path) -> Result<Option<String>, String> {
+    let path = root.join(REPO_SETTINGS_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    match read_yaml(&path)?.get("adapter") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(name)) => Ok(Some(name.clone())),
+        Some(other) => Err(format!(
+            "`adapter` in {} must be a string, found {}",
+            path.display(),
+            type_name(other)
+        )),
+    }
+}
+
+/// Branch filters for one adapter-generated command, from `commands:` in
+/// `<root>/.lhm.yml`. Each pattern becomes a lefthook `{ref: <pattern>}`
+/// condition under the command's `only` or `skip`.
+#[derive(Debug, PartialEq)]
+pub struct RefFilter {
+    pub command: String,
+    pub only: Vec<String>,
+    pub skip: Vec<String>,
+}
+
+/// The `only_ref`/`skip_ref` filters set per command in `<root>/.lhm.yml`.
+pub fn ref_filters(root: &Path) -> Result<Vec<RefFilter>, String> {
+    let path = root.join(REPO_SETTINGS_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let settings = read_yaml(&path)?;
+    let commands = match settings.get("commands") {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Mapping(m)) => m,
+        Some(other) => {
+            return Err(format!(
+                "`commands` in {} must be a mapping, found {}",
+                path.display(),
+                type_name(other)
+            ));
+        }
+    };
+    let mut filters = Vec::new();
+    for (name, fields) in commands {
+        let Some(command) = name.as_str() else {
+            continue;
+        };
+        let patterns = |key: &str| match fields.get(key) {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(Value::String(p)) => Ok(vec![p.clone()]),
+            Some(Value::Sequence(seq)) if seq.iter().all(Value::is_string) => {
+                Ok(seq.iter().filter_map(Value::as_str).map(String::from).collect())
+            }
+            Some(other) => Err(format!(
+                "`{key}` for command `{command}` in {} must be a string or list of strings, found {}",
+                path.display(),
+                type_name(other)
+            )),
+        };
+        filters.push(RefFilter {
+            command: command.to_string(),
+            only: patterns("only_ref")?,
+            skip: patterns("skip_ref")?,
+        });
+    }
+    Ok(filters)
+}
+
+/// Remove the `lhm` settings key so lefthook doesn't see it.
+pub fn strip_lhm_settings(config: Value) -> Value {
+    match config {
+        Value::Mapping(mut m) => {
+            m.remove(LHM_SETTINGS_KEY);
+            Value::Mapping(m)
+        }
+        other => other,
+    }
+}
+
+/// Config fragments in `<root>/lefthook.d/`, sorted by file name.
+pub fn config_fragments(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root.join("lefthook.d")) else {
+        return Vec::new();
+    };
+    let mut fragments: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| LEFTHOOK_EXTENSIONS.contains(&e))
+        })
+        .collect();
+    fragments.sort();
+    fragments
+}
+
+/// Load the repo config at `path` (if any) and merge every `lefthook.d/`
+/// fragment on top of it in file name order. Fragments alone count as a repo
+/// config when there is no main file.
+pub fn load_repo_config(root: Option<&Path>, path: Option<&Path>) -> Result<Option<Value>, String> {
+    let mut config = path.map(read_yaml).transpose()?;
+    for fragment in root.map(config_fragments).unwrap_or_default() {
+        debug!("merging config fragment: {}", fragment.display());
+        let fv = read_yaml(&fragment)?;
+        config = Some(match config {
+            Some(c) => merge_configs(c, fv),
+            None => fv,
+        });
+    }
+    Ok(config)
+}
+
+/// With `tolerant` (`LHM_TOLERANT=1`), turn a failed load of the `layer`
+/// config into a warning so the hook can carry on without it.
+///
+/// Returns the loaded config and whether it was skipped because of an error.
+pub fn tolerate_load_error(
+    result: Result<Option<Value>, String>,
+    tolerant: bool,
+    layer: &str,
+) -> Result<(Option<Value>, bool), String> {
+    match result {
+        Ok(config) => Ok((config, false)),
+        Err(e) if tolerant => {
+            warn!("{e}; continuing without the {layer} config (LHM_TOLERANT)");
+            Ok((None, true))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Resolve the config file `lhm edit` should open.
+///
+/// For the global config this is the active file, or `~/.lefthook.yaml` if none
+/// exists yet. For the repo config (`repo = true`) it is the active repo config,
+/// or `<root>/lefthook.yml`.
+pub fn edit_path(home: &Path, root: Option<&Path>, repo: bool, overrides: &ConfigOverrides) -> Result<PathBuf, String> {
+    if !repo {
+        return Ok(global_config(home, overrides).unwrap_or_else(|| home.join(".lefthook.yaml")));
+    }
+    if let Some(p) = &overrides.local_config {
+        return Ok(p.clone());
+    }
+    let root = root.ok_or("not inside a git repository")?;
+    Ok(repo_config(root, overrides).unwrap_or_else(|| root.join("lefthook.yml")))
+}
+
+/// Labelled paths of the effective configs for `lhm config-path`: the global
+/// config (or `<built-in default>`), plus the repo config when inside a repo.
+pub fn config_path_report(
+    home: &Path,
+    root: Option<&Path>,
+    overrides: &ConfigOverrides,
+) -> Vec<(&'static str, String)> {
+    let display = |p: PathBuf| std::path::absolute(&p).unwrap_or(p).display().to_string();
+    let mut report = vec![(
+        "global",
+        global_config(home, overrides).map_or_else(|| "<built-in default>".to_string(), display),
+    )];
+    if let Some(root) = root {
+        report.push((
+            "repo",
+            repo_config(root, overrides).map_or_else(|| "<none>".to_string(), display),
+        ));
+    }
+    report
+}
+
+/// Write the default global config if no global config exists.
+/// Writes to `~/.lefthook.yaml` unless `target` is given.
+pub fn install_default_global_config(home: &Path, target: Option<&Path>, profile: ConfigProfile) -> Result<(), String> {
+    if let Some(t) = target {
+        let ext = t.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !LEFTHOOK_EXTENSIONS.contains(&ext) {
+            return Err(format!(
+                "invalid config path {}: extension must be one of {}",
+                t.display(),
+                LEFTHOOK_EXTENSIONS.join(", ")
+            ));
+        }
+    }
+    if find_config(home, false).is_some() || target.is_some_and(|t| t.exists()) {
         debug!("global config already exists, skipping default");
         return Ok(());
     }
-    let path = home.join(".lefthook.yaml");
-    fs::write(&path, DEFAULT_GLOBAL_CONFIG).map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    let path = target.map_or_else(|| home.join(".lefthook.yaml"), Path::to_path_buf);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+    }
+    write_atomic(&path, profile.template())?;
     info!("created default global config at {}", path.display());
+    if find_config(home, false).as_deref() != Some(path.as_path()) {
+        info!("set LHM_GLOBAL_CONFIG={} so lhm uses it", path.display());
+    }
+    Ok(())
+}
+
+/// Write `content` to `path` through a temp file in the same directory that is
+/// renamed into place, so an interrupted write never leaves a truncated file.
+/// An existing file keeps its permissions; a new one is readable by everyone.
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let mut tmp =
+        NamedTempFile::new_in(dir).map_err(|e| format!("failed to create temp file in {}: {e}", dir.display()))?;
+    tmp.write_all(content.as_bytes())
+        .and_then(|()| tmp.as_file().sync_all())
+        .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    let permissions = fs::metadata(path).map_or_else(|_| fs::Permissions::from_mode(0o644), |m| m.permissions());
+    tmp.as_file()
+        .set_permissions(permissions)
+        .map_err(|e| format!("failed to set permissions on {}: {e}", path.display()))?;
+    tmp.persist(path)
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e.error))?;
     Ok(())
 }
 
 /// Load the global config from `~/.lefthook.yaml` (or override) if it exists.
 pub fn load_global_config(home: &Path, overrides: &ConfigOverrides) -> Result<Option<Value>, String> {
     match global_config(home, overrides) {
-        Some(path) => read_yaml(&path).map(Some),
+        Some(path) => match read_yaml(&path)? {
+            // An empty file is fine; anything else must be a mapping of hooks/settings.
+            v @ (Value::Mapping(_) | Value::Null) => {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                apply_includes(v, dir, home).map(Some)
+            }
+            other => Err(format!(
+                "global config must be a YAML mapping, found {}: {}",
+                type_name(&other),
+                path.display()
+            )),
+        },
         None => {
             debug!("no global config file found");
             Ok(None)
@@ -120,14 +506,159 @@ pub fn load_global_config(home: &Path, overrides: &ConfigOverrides) -> Result<Op
     }
 }
 
+/// Merge the files listed under `lhm.include` in `config`, in order, and then
+/// `config` itself on top, so the including file's own settings win.
+///
+/// Relative paths resolve against `dir` (the config's directory) and a leading
+/// `~/` against `home`. Included files are not themselves searched for
+/// `lhm.include`.
+fn apply_includes(config: Value, dir: &Path, home: &Path) -> Result<Value, String> {
+    let includes = match config.get(LHM_SETTINGS_KEY).and_then(|lhm| lhm.get("include")) {
+        None | Some(Value::Null) => return Ok(config),
+        Some(Value::Sequence(paths)) => paths.clone(),
+        Some(other) => {
+            return Err(format!(
+                "`lhm.include` must be a list of paths, found {}",
+                type_name(other)
+            ));
+        }
+    };
+    let mut merged: Option<Value> = None;
+    for include in &includes {
+        let Some(include) = include.as_str() else {
+            return Err(format!(
+                "`lhm.include` entries must be paths, found {}",
+                type_name(include)
+            ));
+        };
+        let path = match include.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => dir.join(include),
+        };
+        debug!("global config include: {}", path.display());
+        let value = match read_yaml(&path)? {
+            v @ (Value::Mapping(_) | Value::Null) => v,
+            other => {
+                return Err(format!(
+                    "included config must be a YAML mapping, found {}: {}",
+                    type_name(&other),
+                    path.display()
+                ));
+            }
+        };
+        merged = Some(match merged {
+            Some(m) => merge_configs(m, value),
+            None => value,
+        });
+    }
+    Ok(match merged {
+        Some(m) => merge_configs(m, config),
+        None => config,
+    })
+}
+
+/// Convert CRLF (and stray CR) line endings to LF, so content from Windows
+/// checkouts doesn't leak `\r` into generated commands.
+pub fn normalize_newlines(content: &str) -> String {
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 pub fn read_yaml(path: &Path) -> Result<Value, String> {
     let content = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
-    serde_yaml::from_str(&content).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+    parse_config(&content, path)
+}
+
+/// Read a config from `reader` (e.g. stdin). There is no extension to go by,
+/// so it is parsed as YAML, which also covers plain JSON.
+pub fn read_config_from(mut reader: impl io::Read) -> Result<Value, String> {
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .map_err(|e| format!("failed to read {STDIN_NAME}: {e}"))?;
+    parse_config(&content, Path::new(STDIN_NAME))
+}
+
+/// How a config read from stdin is named in messages.
+pub const STDIN_NAME: &str = "<stdin>";
+
+/// Parse config `content`, dispatching on the extension of `path`, which is
+/// also used to name it in errors.
+fn parse_config(content: &str, path: &Path) -> Result<Value, String> {
+    let mut content = normalize_newlines(content);
+    if path.extension().is_some_and(|e| e == "jsonc") {
+        content = strip_jsonc(&content);
+    }
+    // Documents separated by `---` are merged in order, later ones winning
+    let mut merged: Option<Value> = None;
+    for document in serde_yaml::Deserializer::from_str(&content) {
+        let mut value = Value::deserialize(document).map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+        // Aliases are expanded by the parser; `<<: *anchor` merge keys are not, so
+        // resolve them here so merging sees the effective fields.
+        value
+            .apply_merge()
+            .map_err(|e| format!("failed to resolve merge keys in {}: {e}", path.display()))?;
+        merged = Some(match merged {
+            Some(m) => merge_configs(m, value),
+            None => value,
+        });
+    }
+    Ok(merged.unwrap_or(Value::Null))
+}
+
+/// Turn JSON-with-comments into plain JSON by removing `//` and `/* */`
+/// comments and trailing commas. String literals are left untouched.
+fn strip_jsonc(input: &str) -> String {
+    let without_comments = strip_outside_strings(input, |rest| {
+        if rest.starts_with("//") {
+            return Some(rest.find('\n').unwrap_or(rest.len()));
+        }
+        let block = rest.strip_prefix("/*")?;
+        Some(block.find("*/").map_or(rest.len(), |i| i + 4))
+    });
+    strip_outside_strings(&without_comments, |rest| {
+        let after = rest.strip_prefix(',')?.trim_start();
+        (after.starts_with('}') || after.starts_with(']')).then_some(1)
+    })
+}
+
+/// Copy `input`, skipping spans outside of string literals for which `skip`
+/// returns the number of bytes to drop.
+fn strip_outside_strings(input: &str, skip: impl Fn(&str) -> Option<usize>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+    while i < input.len() {
+        let rest = &input[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if let Some(n) = skip(rest) {
+            i += n;
+            continue;
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// The YAML `write_merged_temp` writes for `merged`.
+pub fn merged_yaml(merged: &Value) -> Result<String, String> {
+    serde_yaml::to_string(merged).map_err(|e| format!("failed to serialize config: {e}"))
 }
 
 /// Serialize a merged config value to a temp file for lefthook.
 pub fn write_merged_temp(merged: Value) -> Result<NamedTempFile, String> {
-    let content = serde_yaml::to_string(&merged).map_err(|e| format!("failed to serialize config: {e}"))?;
+    let content = merged_yaml(&merged)?;
     debug!("merged config:\n{content}");
 
     let mut tmp = tempfile::Builder::new()
@@ -207,10 +738,131 @@ mod tests {
         assert_eq!(find_config(dir.path(), false), Some(dir.path().join("lefthook.yml")));
     }
 
+    #[test]
+    fn test_is_ci() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+        };
+        assert!(!is_ci(env(&[])));
+        assert!(is_ci(env(&[("CI", "true")])));
+        assert!(is_ci(env(&[("GITHUB_ACTIONS", "true")])));
+        assert!(is_ci(env(&[("JENKINS_URL", "https://ci.example.com/")])));
+        assert!(!is_ci(env(&[("CI", "false")])));
+        assert!(!is_ci(env(&[("CI", "")])));
+        assert!(is_ci(env(&[("LHM_CI", "1")])));
+        assert!(!is_ci(env(&[("LHM_CI", "0"), ("GITLAB_CI", "true")])));
+    }
+
+    #[test]
+    fn test_read_yaml_merges_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lefthook.yml");
+        fs::write(
+            &path,
+            "colors: false\npre-commit:\n  commands:\n    lint:\n      run: lint\n---\ncolors: true\npre-commit:\n  commands:\n    fmt:\n      run: fmt\n",
+        )
+        .unwrap();
+        let config = read_yaml(&path).unwrap();
+        assert_eq!(config["colors"], Value::Bool(true));
+        let commands = config["pre-commit"]["commands"].as_mapping().unwrap();
+        assert_eq!(commands.len(), 2, "{config:?}");
+
+        fs::write(&path, "---\npre-push:\n  parallel: true\n").unwrap();
+        assert_eq!(read_yaml(&path).unwrap()["pre-push"]["parallel"], Value::Bool(true));
+
+        fs::write(&path, "pre-push: {}\n---\npre-push: [\n").unwrap();
+        let err = read_yaml(&path).unwrap_err();
+        assert!(err.starts_with("failed to parse"), "{err}");
+    }
+
+    #[test]
+    fn test_read_config_from_reader() {
+        let config = read_config_from(io::Cursor::new(
+            "base: &base\n  run: lint\npre-commit:\n  commands:\n    lint:\n      <<: *base\n      glob: '*.rs'\n",
+        ))
+        .unwrap();
+        assert_eq!(config["pre-commit"]["commands"]["lint"]["run"].as_str(), Some("lint"));
+
+        let config = read_config_from(io::Cursor::new(r#"{"pre-push": {"commands": {}}}"#)).unwrap();
+        assert!(config["pre-push"]["commands"].is_mapping());
+
+        let err = read_config_from(io::Cursor::new("pre-commit: [")).unwrap_err();
+        assert!(err.starts_with("failed to parse <stdin>"), "{err}");
+    }
+
+    #[test]
+    fn test_read_yaml_jsonc_comments_and_trailing_commas() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lefthook.jsonc");
+        fs::write(
+            &path,
+            r#"{
+  // line comment
+  "pre-commit": {
+    /* block
+       comment */
+    "commands": {
+      "fmt": { "run": "just fmt", },
+    },
+  },
+  "output": ["success", "failure",],
+}
+"#,
+        )
+        .unwrap();
+        let config = read_yaml(&path).unwrap();
+        assert_eq!(
+            config["pre-commit"]["commands"]["fmt"]["run"].as_str(),
+            Some("just fmt")
+        );
+        assert_eq!(config["output"].as_sequence().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_comment_markers_in_strings() {
+        let input = r#"{"run": "curl https://example.com // not a comment", "x": "/* nor this */", "q": "a\"//b"}"#;
+        assert_eq!(strip_jsonc(input), input);
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_commas_in_strings() {
+        let input = r#"{"run": "echo ,}"}"#;
+        assert_eq!(strip_jsonc(input), input);
+    }
+
+    #[test]
+    fn test_find_config_bare_name_beats_dotted_any_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".lefthook.yml"), "").unwrap();
+        fs::write(dir.path().join("lefthook.toml"), "").unwrap();
+        assert_eq!(find_config(dir.path(), false), Some(dir.path().join("lefthook.toml")));
+    }
+
+    #[test]
+    fn test_find_config_dotted_beats_dot_config_any_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".config")).unwrap();
+        fs::write(dir.path().join(".config/lefthook.yml"), "").unwrap();
+        fs::write(dir.path().join(".lefthook.jsonc"), "").unwrap();
+        assert_eq!(find_config(dir.path(), true), Some(dir.path().join(".lefthook.jsonc")));
+    }
+
+    #[test]
+    fn test_find_config_dot_config_extension_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".config")).unwrap();
+        fs::write(dir.path().join(".config/lefthook.json"), "").unwrap();
+        fs::write(dir.path().join(".config/lefthook.yaml"), "").unwrap();
+        assert_eq!(
+            find_config(dir.path(), true),
+            Some(dir.path().join(".config/lefthook.yaml"))
+        );
+    }
+
     #[test]
     fn test_install_default_global_config_creates_when_missing() {
         let dir = tempfile::tempdir().unwrap();
-        install_default_global_config(dir.path()).unwrap();
+        install_default_global_config(dir.path(), None, ConfigProfile::Full).unwrap();
 
         let created = dir.path().join(".lefthook.yaml");
         assert!(created.is_file());
@@ -219,13 +871,61 @@ mod tests {
         assert!(content.contains("pre-commit:"));
     }
 
+    #[test]
+    fn test_install_default_global_config_is_complete_and_valid() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        install_default_global_config(dir.path(), None, ConfigProfile::Full).unwrap();
+
+        let created = dir.path().join(".lefthook.yaml");
+        assert_eq!(fs::read_to_string(&created).unwrap(), DEFAULT_GLOBAL_CONFIG);
+        assert!(read_yaml(&created).unwrap().get("pre-commit").is_some());
+        assert_eq!(fs::metadata(&created).unwrap().permissions().mode() & 0o777, 0o644);
+        let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1, "temp file left behind: {entries:?}");
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_and_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lefthook.yaml");
+        fs::write(&path, "old: true\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, "new: true\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new: true\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(write_atomic(&dir.path().join("missing/lefthook.yaml"), "").is_err());
+    }
+
+    #[test]
+    fn test_install_default_global_config_profiles() {
+        let mut written = Vec::new();
+        for profile in [ConfigProfile::Full, ConfigProfile::Minimal] {
+            let dir = tempfile::tempdir().unwrap();
+            install_default_global_config(dir.path(), None, profile).unwrap();
+            let path = dir.path().join(".lefthook.yaml");
+            let content = fs::read_to_string(&path).unwrap();
+            assert_eq!(content, profile.template());
+            let config = read_yaml(&path).unwrap();
+            assert!(config.get("pre-commit").is_some(), "{profile:?}");
+            assert!(crate::validate::validate_config(&config).is_empty(), "{profile:?}");
+            written.push(content);
+        }
+        assert_ne!(written[0], written[1]);
+        assert!(!written[1].contains("just"), "minimal profile needs no tools");
+    }
+
     #[test]
     fn test_install_default_global_config_skips_when_exists() {
         let dir = tempfile::tempdir().unwrap();
         let existing = dir.path().join("lefthook.yml");
         fs::write(&existing, "custom: true\n").unwrap();
 
-        install_default_global_config(dir.path()).unwrap();
+        install_default_global_config(dir.path(), None, ConfigProfile::Full).unwrap();
 
         // Original file untouched
         assert_eq!(fs::read_to_string(&existing).unwrap(), "custom: true\n");
@@ -233,6 +933,35 @@ mod tests {
         assert!(!dir.path().join(".lefthook.yaml").exists());
     }
 
+    #[test]
+    fn test_install_default_global_config_custom_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join(".config/lefthook.yaml");
+        install_default_global_config(dir.path(), Some(&target), ConfigProfile::Full).unwrap();
+
+        assert!(target.is_file());
+        assert_eq!(fs::read_to_string(&target).unwrap(), DEFAULT_GLOBAL_CONFIG);
+        assert!(!dir.path().join(".lefthook.yaml").exists());
+    }
+
+    #[test]
+    fn test_install_default_global_config_custom_path_skips_when_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".lefthook.yml"), "custom: true\n").unwrap();
+        let target = dir.path().join("custom.yaml");
+        install_default_global_config(dir.path(), Some(&target), ConfigProfile::Full).unwrap();
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_install_default_global_config_rejects_unknown_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("lefthook.txt");
+        let err = install_default_global_config(dir.path(), Some(&target), ConfigProfile::Full).unwrap_err();
+        assert!(err.contains("extension must be one of"), "{err}");
+        assert!(!target.exists());
+    }
+
     #[test]
     fn test_load_global_config_returns_none_when_missing() {
         let dir = tempfile::tempdir().unwrap();
@@ -240,6 +969,17 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_load_global_config_rejects_sequence() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".lefthook.yaml"), "- pre-commit\n- pre-push\n").unwrap();
+        let err = load_global_config(dir.path(), &no_overrides()).unwrap_err();
+        assert!(
+            err.contains("global config must be a YAML mapping, found sequence"),
+            "{err}"
+        );
+    }
+
     #[test]
     fn test_load_global_config_returns_some_when_exists() {
         let dir = tempfile::tempdir().unwrap();
@@ -254,6 +994,100 @@ mod tests {
         assert!(out.contains("pre-commit:"));
     }
 
+    #[test]
+    fn test_load_global_config_includes_in_order() {
+        let home = tempfile::tempdir().unwrap();
+        let conf = home.path().join("conf");
+        fs::create_dir_all(&conf).unwrap();
+        fs::write(
+            conf.join("base.yaml"),
+            "pre-commit:\n  commands:\n    fmt:\n      run: base-fmt\n    lint:\n      run: base-lint\n",
+        )
+        .unwrap();
+        fs::write(
+            conf.join("rust.json"),
+            r#"{"pre-commit": {"commands": {"lint": {"run": "clippy"}}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            conf.join("global.yaml"),
+            "lhm:\n  include: [base.yaml, rust.json]\npre-commit:\n  commands:\n    fmt:\n      run: own-fmt\n",
+        )
+        .unwrap();
+        let overrides = ConfigOverrides {
+            global_config: Some(conf.join("global.yaml")),
+            ..Default::default()
+        };
+
+        let config = load_global_config(home.path(), &overrides).unwrap().unwrap();
+        let commands = &config["pre-commit"]["commands"];
+        assert_eq!(commands["lint"]["run"].as_str(), Some("clippy"));
+        assert_eq!(commands["fmt"]["run"].as_str(), Some("own-fmt"));
+        assert!(strip_lhm_settings(config).get("lhm").is_none());
+    }
+
+    #[test]
+    fn test_load_global_config_include_expands_home() {
+        let home = tempfile::tempdir().unwrap();
+        fs::create_dir_all(home.path().join(".lefthook.d")).unwrap();
+        fs::write(
+            home.path().join(".lefthook.d").join("rust.yaml"),
+            "pre-push:\n  commands:\n    test:\n      run: cargo test\n",
+        )
+        .unwrap();
+        fs::write(
+            home.path().join(".lefthook.yaml"),
+            "lhm:\n  include: [~/.lefthook.d/rust.yaml]\n",
+        )
+        .unwrap();
+
+        let config = load_global_config(home.path(), &no_overrides()).unwrap().unwrap();
+        assert_eq!(
+            config["pre-push"]["commands"]["test"]["run"].as_str(),
+            Some("cargo test")
+        );
+    }
+
+    #[test]
+    fn test_load_global_config_missing_include() {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(home.path().join(".lefthook.yaml"), "lhm:\n  include: [nope.yaml]\n").unwrap();
+        let err = load_global_config(home.path(), &no_overrides()).unwrap_err();
+        assert!(err.contains("failed to read") && err.contains("nope.yaml"), "{err}");
+    }
+
+    #[test]
+    fn test_tolerate_unreadable_repo_config() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory where the file should be fails to read, like a file
+        // mid-rewrite or without read permission.
+        let path = dir.path().join("lefthook.yml");
+        fs::create_dir_all(&path).unwrap();
+
+        let result = load_repo_config(Some(dir.path()), Some(&path));
+        assert!(tolerate_load_error(result, false, "repo").is_err());
+
+        let result = load_repo_config(Some(dir.path()), Some(&path));
+        assert_eq!(tolerate_load_error(result, true, "repo").unwrap(), (None, true));
+    }
+
+    #[test]
+    fn test_tolerate_global_parse_error() {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(home.path().join(".lefthook.yaml"), "pre-commit: [unclosed\n").unwrap();
+
+        let err = tolerate_load_error(load_global_config(home.path(), &no_overrides()), false, "global").unwrap_err();
+        assert!(err.contains("failed to parse"), "{err}");
+        let (config, skipped) =
+            tolerate_load_error(load_global_config(home.path(), &no_overrides()), true, "global").unwrap();
+        assert!(config.is_none() && skipped);
+
+        fs::write(home.path().join(".lefthook.yaml"), "skip_lfs: true\n").unwrap();
+        let (config, skipped) =
+            tolerate_load_error(load_global_config(home.path(), &no_overrides()), true, "global").unwrap();
+        assert!(config.is_some() && !skipped);
+    }
+
     #[test]
     fn test_global_config_override() {
         let dir = tempfile::tempdir().unwrap();
@@ -282,6 +1116,317 @@ mod tests {
         assert_eq!(result, Some(override_path));
     }
 
+    #[test]
+    fn test_disabled_adapters_from_global_config() {
+        let global: Value = serde_yaml::from_str("lhm:\n  disable_adapters: [hooks-dir, husky]\n").unwrap();
+        let disabled = disabled_adapters(Some(&global));
+        assert!(disabled.contains(&"hooks-dir".to_string()));
+        assert!(disabled.contains(&"husky".to_string()));
+    }
+
+    #[test]
+    fn test_pinned_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(pinned_adapter(dir.path()).unwrap(), None);
+
+        fs::write(dir.path().join(".lhm.yml"), "adapter: hooks-dir\n").unwrap();
+        assert_eq!(pinned_adapter(dir.path()).unwrap().as_deref(), Some("hooks-dir"));
+
+        fs::write(dir.path().join(".lhm.yml"), "adapter: [husky]\n").unwrap();
+        let err = pinned_adapter(dir.path()).unwrap_err();
+        assert!(err.contains("must be a string, found sequence"), "{err}");
+    }
+
+    #[test]
+    fn test_ref_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(ref_filters(dir.path()).unwrap(), []);
+
+        fs::write(
+            dir.path().join(".lhm.yml"),
+            "adapter: hooks-dir\ncommands:\n  test:\n    only_ref: main\n  e2e:\n    skip_ref: [feature/*, wip/*]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ref_filters(dir.path()).unwrap(),
+            [
+                RefFilter {
+                    command: "test".into(),
+                    only: vec!["main".into()],
+                    skip: vec![],
+                },
+                RefFilter {
+                    command: "e2e".into(),
+                    only: vec![],
+                    skip: vec!["feature/*".into(), "wip/*".into()],
+                },
+            ]
+        );
+
+        fs::write(dir.path().join(".lhm.yml"), "commands:\n  test:\n    only_ref: true\n").unwrap();
+        let err = ref_filters(dir.path()).unwrap_err();
+        assert!(err.contains("`only_ref` for command `test`"), "{err}");
+    }
+
+    #[test]
+    fn test_lhm_flag() {
+        let on: Value = serde_yaml::from_str("lhm:\n  merge_adapters: true\n").unwrap();
+        let off: Value = serde_yaml::from_str("lhm:\n  merge_adapters: false\n").unwrap();
+        let unset: Value = serde_yaml::from_str("skip_lfs: true\n").unwrap();
+        let flag = |g, r| lhm_flag(g, r, "merge_adapters");
+        assert!(!flag(None, None));
+        assert!(flag(Some(&on), Some(&unset)));
+        assert!(flag(Some(&off), Some(&on)));
+        assert!(!flag(Some(&on), Some(&off)));
+        assert!(!lhm_flag(Some(&on), None, "nested_configs"));
+    }
+
+    #[test]
+    fn test_merge_strategy_setting() {
+        let deep: Value = serde_yaml::from_str("lhm:\n  merge_strategy: deep\n").unwrap();
+        let global_wins: Value = serde_yaml::from_str("lhm:\n  merge_strategy: global-wins\n").unwrap();
+        let bad: Value = serde_yaml::from_str("lhm:\n  merge_strategy: [deep]\n").unwrap();
+        assert_eq!(merge_strategy(None, None), Ok(MergeStrategy::RepoWins));
+        assert_eq!(merge_strategy(Some(&deep), None), Ok(MergeStrategy::Deep));
+        assert_eq!(
+            merge_strategy(Some(&deep), Some(&global_wins)),
+            Ok(MergeStrategy::GlobalWins)
+        );
+        assert!(
+            merge_strategy(Some(&bad), None)
+                .unwrap_err()
+                .contains("must be a string")
+        );
+    }
+
+    #[test]
+    fn test_nested_config_paths() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let app = root.join("services").join("app");
+        fs::create_dir_all(app.join("src")).unwrap();
+        fs::write(root.join("lefthook.yml"), "").unwrap();
+        fs::write(root.join("services").join("lefthook.yml"), "").unwrap();
+        fs::write(app.join(".lefthook.yaml"), "").unwrap();
+
+        assert_eq!(
+            nested_config_paths(root, &app.join("src")),
+            [root.join("services").join("lefthook.yml"), app.join(".lefthook.yaml")]
+        );
+        assert!(nested_config_paths(root, root).is_empty());
+        assert!(nested_config_paths(&app, root).is_empty());
+    }
+
+    #[test]
+    fn test_merge_nested_configs_closest_wins() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let app = root.join("services").join("app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(
+            root.join("services").join("lefthook.yml"),
+            "pre-commit:\n  commands:\n    lint:\n      run: services-lint\n    test:\n      run: services-test\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("lefthook.yml"),
+            "pre-commit:\n  commands:\n    lint:\n      run: app-lint\n",
+        )
+        .unwrap();
+        let repo: Value = serde_yaml::from_str(
+            "pre-commit:\n  commands:\n    lint:\n      run: root-lint\n    fmt:\n      run: root-fmt\n",
+        )
+        .unwrap();
+
+        let merged = merge_nested_configs(Some(repo), root, &app).unwrap().unwrap();
+        let commands = &merged["pre-commit"]["commands"];
+        assert_eq!(commands["lint"]["run"].as_str(), Some("app-lint"));
+        assert_eq!(commands["test"]["run"].as_str(), Some("services-test"));
+        assert_eq!(commands["fmt"]["run"].as_str(), Some("root-fmt"));
+    }
+
+    #[test]
+    fn test_strip_lhm_settings() {
+        let config: Value = serde_yaml::from_str("lhm:\n  disable_adapters: [husky]\nskip_lfs: true\n").unwrap();
+        let stripped = strip_lhm_settings(config);
+        assert!(stripped.get("lhm").is_none());
+        assert_eq!(stripped["skip_lfs"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_config_path_report_no_configs() {
+        let home = tempfile::tempdir().unwrap();
+        let report = config_path_report(home.path(), None, &no_overrides());
+        assert_eq!(report, vec![("global", "<built-in default>".to_string())]);
+    }
+
+    #[test]
+    fn test_config_path_report_global_and_repo() {
+        let home = tempfile::tempdir().unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        let global = home.path().join(".lefthook.yaml");
+        fs::write(&global, "{}").unwrap();
+        let local = repo.path().join("lefthook.yml");
+        fs::write(&local, "{}").unwrap();
+
+        let report = config_path_report(home.path(), Some(repo.path()), &no_overrides());
+        assert_eq!(
+            report,
+            vec![
+                ("global", global.display().to_string()),
+                ("repo", local.display().to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_path_report_repo_without_config() {
+        let home = tempfile::tempdir().unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        let report = config_path_report(home.path(), Some(repo.path()), &no_overrides());
+        assert_eq!(report[1], ("repo", "<none>".to_string()));
+    }
+
+    #[test]
+    fn test_read_yaml_crlf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lefthook.yml");
+        fs::write(
+            &path,
+            "pre-commit:\r\n  commands:\r\n    lint:\r\n      run: |\r\n        a\r\n        b\r\n",
+        )
+        .unwrap();
+        let config = read_yaml(&path).unwrap();
+        assert_eq!(config["pre-commit"]["commands"]["lint"]["run"].as_str(), Some("a\nb\n"));
+    }
+
+    #[test]
+    fn test_read_yaml_resolves_anchors_through_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("global.yml");
+        fs::write(
+            &global,
+            r#"
+x-lint: &lint
+  run: shared-lint {staged_files}
+  glob: "*.rs"
+pre-commit:
+  commands:
+    lint:
+      <<: *lint
+    lint-tests:
+      <<: *lint
+      root: tests/
+pre-push:
+  commands:
+    lint: *lint
+"#,
+        )
+        .unwrap();
+        let repo = dir.path().join("repo.yml");
+        fs::write(
+            &repo,
+            "pre-commit:
+  commands:
+    lint:
+      run: repo-lint
+",
+        )
+        .unwrap();
+
+        let merged = merge_configs(read_yaml(&global).unwrap(), read_yaml(&repo).unwrap());
+        let commit = &merged["pre-commit"]["commands"];
+        assert_eq!(commit["lint"]["run"].as_str(), Some("repo-lint"));
+        assert_eq!(commit["lint-tests"]["run"].as_str(), Some("shared-lint {staged_files}"));
+        assert_eq!(commit["lint-tests"]["glob"].as_str(), Some("*.rs"));
+        assert!(commit["lint-tests"].get("<<").is_none());
+        assert_eq!(
+            merged["pre-push"]["commands"]["lint"]["run"].as_str(),
+            Some("shared-lint {staged_files}")
+        );
+    }
+
+    #[test]
+    fn test_load_repo_config_merges_fragments_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("lefthook.yml");
+        fs::write(&main, "pre-commit:\n  commands:\n    fmt:\n      run: main-fmt\n").unwrap();
+        let frag_dir = dir.path().join("lefthook.d");
+        fs::create_dir_all(&frag_dir).unwrap();
+        fs::write(
+            frag_dir.join("20-late.yml"),
+            "pre-commit:\n  commands:\n    lint:\n      run: late-lint\n",
+        )
+        .unwrap();
+        fs::write(
+            frag_dir.join("10-early.yaml"),
+            "pre-commit:\n  commands:\n    fmt:\n      run: early-fmt\n    lint:\n      run: early-lint\n",
+        )
+        .unwrap();
+        fs::write(frag_dir.join("notes.txt"), "ignored").unwrap();
+
+        let config = load_repo_config(Some(dir.path()), Some(&main)).unwrap().unwrap();
+        let cmds = &config["pre-commit"]["commands"];
+        assert_eq!(cmds["fmt"]["run"].as_str(), Some("early-fmt"));
+        assert_eq!(cmds["lint"]["run"].as_str(), Some("late-lint"));
+    }
+
+    #[test]
+    fn test_load_repo_config_fragments_without_main() {
+        let dir = tempfile::tempdir().unwrap();
+        let frag_dir = dir.path().join("lefthook.d");
+        fs::create_dir_all(&frag_dir).unwrap();
+        fs::write(
+            frag_dir.join("a.yml"),
+            "pre-push:\n  commands:\n    test:\n      run: t\n",
+        )
+        .unwrap();
+
+        let config = load_repo_config(Some(dir.path()), None).unwrap().unwrap();
+        assert_eq!(config["pre-push"]["commands"]["test"]["run"].as_str(), Some("t"));
+    }
+
+    #[test]
+    fn test_load_repo_config_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_repo_config(Some(dir.path()), None).unwrap().is_none());
+        assert!(load_repo_config(None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_edit_path_existing_global() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lefthook.yml"), "").unwrap();
+        let path = edit_path(dir.path(), None, false, &no_overrides()).unwrap();
+        assert_eq!(path, dir.path().join("lefthook.yml"));
+    }
+
+    #[test]
+    fn test_edit_path_missing_global_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = edit_path(dir.path(), None, false, &no_overrides()).unwrap();
+        assert_eq!(path, dir.path().join(".lefthook.yaml"));
+    }
+
+    #[test]
+    fn test_edit_path_repo() {
+        let home = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let path = edit_path(home.path(), Some(root.path()), true, &no_overrides()).unwrap();
+        assert_eq!(path, root.path().join("lefthook.yml"));
+
+        fs::create_dir_all(root.path().join(".config")).unwrap();
+        fs::write(root.path().join(".config/lefthook.yaml"), "").unwrap();
+        let path = edit_path(home.path(), Some(root.path()), true, &no_overrides()).unwrap();
+        assert_eq!(path, root.path().join(".config/lefthook.yaml"));
+    }
+
+    #[test]
+    fn test_edit_path_repo_outside_git() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(edit_path(home.path(), None, true, &no_overrides()).is_err());
+    }
+
     #[test]
     fn test_overrides_from_env() {
         let _guard1 = TempEnvVar::set("LHM_GLOBAL_CONFIG", "/tmp/g.yaml");
@@ -0,0 +1,235 @@
+use serde_json::json;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::{ConfigOverrides, load_global_config};
+use crate::hooks::hook_symlink_status;
+
+/// Result of a single `lhm doctor` check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Run all environment checks.
+pub fn run_checks(home: &Path, hooks_dir: &Path, overrides: &ConfigOverrides) -> Vec<Check> {
+    vec![
+        check_tool("git", command_version("git", &["--version"])),
+        check_tool("lefthook", command_version("lefthook", &["--version"])),
+        check_hooks_path(
+            git_config_global("core.hooksPath").as_deref(),
+            hooks_dir,
+            is_lhm_hooks_dir,
+        ),
+        check_global_config(home, overrides),
+    ]
+}
+
+/// Render checks as a JSON object keyed by check name, plus an overall `ok`.
+pub fn checks_json(checks: &[Check]) -> serde_json::Value {
+    let mut out = serde_json::Map::new();
+    for check in checks {
+        out.insert(check.name.to_string(), json!({"ok": check.ok, "detail": check.detail}));
+    }
+    out.insert("ok".to_string(), json!(checks.iter().all(|c| c.ok)));
+    serde_json::Value::Object(out)
+}
+
+/// Run `program args` and return its trimmed stdout, or `None` if it can't be run.
+pub fn command_version(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
+
+/// Versions for `lhm version`: lhm's own, then those reported by the given
+/// `lefthook` and `git` programs, or "not found" when one can't be run.
+pub fn versions(lefthook: &str, git: &str) -> Vec<(&'static str, String)> {
+    let found = |v: Option<String>| v.unwrap_or_else(|| "not found".to_string());
+    vec![
+        ("lhm", env!("CARGO_PKG_VERSION").to_string()),
+        ("lefthook", found(command_version(lefthook, &["version"]))),
+        ("git", found(command_version(git, &["--version"]))),
+    ]
+}
+
+/// Oldest git (major, minor) that honors `core.hooksPath`.
+pub const MIN_HOOKS_PATH_GIT: (u32, u32) = (2, 9);
+
+/// Major and minor version from `git --version` output, e.g. `git version
+/// 2.43.0` or `git version 2.39.3 (Apple Git-146)`.
+pub fn parse_git_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.strip_prefix("git version ")?.split_whitespace().next()?;
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// The installed git's (major, minor) version, if it can be determined.
+pub fn git_version() -> Option<(u32, u32)> {
+    command_version("git", &["--version"])
+        .as_deref()
+        .and_then(parse_git_version)
+}
+
+fn git_config_global(key: &str) -> Option<String> {
+    git_config("--global", key)
+}
+
+/// Read a git config value from the given scope (`--global`, `--local`).
+pub fn git_config(scope: &str, key: &str) -> Option<String> {
+    command_version("git", &["config", scope, "--get", key])
+}
+
+fn check_tool(name: &'static str, version: Option<String>) -> Check {
+    match version {
+        Some(v) => Check {
+            name,
+            ok: true,
+            detail: v,
+        },
+        None => Check {
+            name,
+            ok: false,
+            detail: "not found in PATH".to_string(),
+        },
+    }
+}
+
+/// Whether `dir` holds hook symlinks to the running lhm binary (e.g. from `install --hooks-dir`).
+fn is_lhm_hooks_dir(dir: &Path) -> bool {
+    std::env::current_exe().is_ok_and(|binary| hook_symlink_status(dir, &binary).current > 0)
+}
+
+fn check_hooks_path(current: Option<&str>, expected: &Path, is_managed: impl Fn(&Path) -> bool) -> Check {
+    let (ok, detail) = match current {
+        Some(p) if Path::new(p) == expected || is_managed(Path::new(p)) => (true, format!("set to {p}")),
+        Some(p) => (false, format!("set to {p}, expected {}", expected.display())),
+        None => (false, "not set (run `lhm install`)".to_string()),
+    };
+    Check {
+        name: "core.hooksPath",
+        ok,
+        detail,
+    }
+}
+
+fn check_global_config(home: &Path, overrides: &ConfigOverrides) -> Check {
+    let (ok, detail) = match load_global_config(home, overrides) {
+        Ok(Some(_)) => (true, "found".to_string()),
+        Ok(None) => (true, "not found (optional)".to_string()),
+        Err(e) => (false, e),
+    };
+    Check {
+        name: "global config",
+        ok,
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_versions_with_fake_lefthook() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let lefthook = dir.path().join("lefthook");
+        fs::write(&lefthook, "#!/bin/sh\n[ \"$1\" = version ] && echo 1.11.2\n").unwrap();
+        fs::set_permissions(&lefthook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let versions = versions(lefthook.to_str().unwrap(), "git");
+        assert_eq!(versions[0], ("lhm", env!("CARGO_PKG_VERSION").to_string()));
+        assert_eq!(versions[1], ("lefthook", "1.11.2".to_string()));
+        assert_eq!(versions[2].0, "git");
+        assert!(versions[2].1.starts_with("git version"), "{:?}", versions[2]);
+    }
+
+    #[test]
+    fn test_versions_lefthook_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("lefthook");
+        let versions = versions(missing.to_str().unwrap(), "git");
+        assert_eq!(versions[1], ("lefthook", "not found".to_string()));
+    }
+
+    #[test]
+    fn test_parse_git_version() {
+        assert_eq!(parse_git_version("git version 2.43.0"), Some((2, 43)));
+        assert_eq!(parse_git_version("git version 2.39.3 (Apple Git-146)"), Some((2, 39)));
+        assert_eq!(parse_git_version("git version 2.45.1.windows.1"), Some((2, 45)));
+        assert_eq!(parse_git_version("git version 1.8.3.1"), Some((1, 8)));
+        assert_eq!(parse_git_version("git version 2"), None);
+        assert_eq!(parse_git_version("hub version 2.14.2"), None);
+        assert!(parse_git_version("git version 2.8.6") < Some(MIN_HOOKS_PATH_GIT));
+    }
+
+    #[test]
+    fn test_check_hooks_path_matches() {
+        let check = check_hooks_path(Some("/home/u/.lhm/hooks"), Path::new("/home/u/.lhm/hooks"), |_| false);
+        assert!(check.ok, "{check:?}");
+    }
+
+    #[test]
+    fn test_check_hooks_path_other_tool() {
+        let check = check_hooks_path(Some("/opt/other/hooks"), Path::new("/home/u/.lhm/hooks"), |_| false);
+        assert!(!check.ok);
+        assert!(check.detail.contains("/opt/other/hooks"), "{check:?}");
+    }
+
+    #[test]
+    fn test_check_hooks_path_custom_lhm_dir() {
+        let check = check_hooks_path(Some("/opt/lhm-hooks"), Path::new("/home/u/.lhm/hooks"), |_| true);
+        assert!(check.ok, "{check:?}");
+    }
+
+    #[test]
+    fn test_check_hooks_path_unset() {
+        let check = check_hooks_path(None, Path::new("/home/u/.lhm/hooks"), |_| true);
+        assert!(!check.ok);
+    }
+
+    #[test]
+    fn test_checks_json_reports_failure() {
+        let checks = [
+            check_tool("git", Some("git version 2.43.0".to_string())),
+            check_tool("lefthook", None),
+        ];
+        let out = checks_json(&checks);
+        let parsed: serde_json::Value = serde_json::from_str(&out.to_string()).unwrap();
+        assert_eq!(parsed["git"], json!({"ok": true, "detail": "git version 2.43.0"}));
+        assert_eq!(parsed["lefthook"]["ok"], json!(false));
+        assert_eq!(parsed["ok"], json!(false));
+    }
+
+    #[test]
+    fn test_check_tool_missing() {
+        let check = check_tool("lefthook", None);
+        assert!(!check.ok);
+        assert_eq!(check.detail, "not found in PATH");
+    }
+
+    #[test]
+    fn test_check_global_config_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".lefthook.yaml"), "pre-commit: [\n").unwrap();
+        let check = check_global_config(dir.path(), &ConfigOverrides::default());
+        assert!(!check.ok);
+        assert!(check.detail.contains("failed to parse"), "{check:?}");
+    }
+
+    #[test]
+    fn test_check_global_config_missing_is_ok() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = check_global_config(dir.path(), &ConfigOverrides::default());
+        assert!(check.ok);
+    }
+}
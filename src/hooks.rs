@@ -1,8 +1,11 @@
-use log::debug;
+use crate::config::{LHM_SETTINGS_KEY, RefFilter};
+use crate::trace;
+use log::{debug, warn};
+use serde_json::json;
 use serde_yaml::Value;
 use std::fs;
 use std::os::unix::fs::symlink;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub const GIT_HOOKS: &[&str] = &[
     "applypatch-msg",
@@ -48,6 +51,169 @@ pub fn create_hook_symlinks(dir: &Path, binary: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Symlink every git hook in a repository's own hooks dir (`.git/hooks`) to
+/// `binary`, for git versions without `core.hooksPath`. Unlike
+/// `create_hook_symlinks`, other files in the dir (e.g. `*.sample`) are left
+/// alone, and existing hook scripts are only replaced when `force` is set.
+pub fn link_git_dir_hooks(dir: &Path, binary: &Path, force: bool) -> Result<(), String> {
+    let scripts: Vec<&str> = GIT_HOOKS
+        .iter()
+        .copied()
+        .filter(|hook| {
+            dir.join(hook)
+                .symlink_metadata()
+                .is_ok_and(|m| !m.file_type().is_symlink())
+        })
+        .collect();
+    if !scripts.is_empty() && !force {
+        return Err(format!(
+            "{} already has hook scripts: {} (use --force to replace them)",
+            dir.display(),
+            scripts.join(", ")
+        ));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    for hook in GIT_HOOKS {
+        let link = dir.join(hook);
+        let _ = fs::remove_file(&link);
+        symlink(binary, &link).map_err(|e| format!("failed to symlink {}: {e}", link.display()))?;
+    }
+    Ok(())
+}
+
+/// Check that a custom hooks dir is absolute and writable, creating it if needed.
+pub fn validate_hooks_dir(dir: &Path) -> Result<(), String> {
+    if !dir.is_absolute() {
+        return Err(format!("hooks dir must be an absolute path: {}", dir.display()));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    tempfile::tempfile_in(dir).map_err(|e| format!("hooks dir is not writable: {}: {e}", dir.display()))?;
+    Ok(())
+}
+
+/// How many `GIT_HOOKS` symlinks exist in a hooks dir and how many resolve to a binary.
+#[derive(Debug, PartialEq)]
+pub struct SymlinkStatus {
+    /// Hooks present as symlinks.
+    pub present: usize,
+    /// Symlinks resolving to the expected binary.
+    pub current: usize,
+}
+
+pub fn hook_symlink_status(dir: &Path, binary: &Path) -> SymlinkStatus {
+    let binary = fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf());
+    let mut status = SymlinkStatus { present: 0, current: 0 };
+    for hook in GIT_HOOKS {
+        let link = dir.join(hook);
+        if !link.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            continue;
+        }
+        status.present += 1;
+        if fs::canonicalize(&link).is_ok_and(|target| target == binary) {
+            status.current += 1;
+        }
+    }
+    status
+}
+
+/// Differences between `dir` and what `create_hook_symlinks(dir, binary)`
+/// would produce: missing hook symlinks and ones that don't resolve to
+/// `binary`, one message per hook.
+pub fn hook_symlink_drift(dir: &Path, binary: &Path) -> Vec<String> {
+    let canonical_binary = fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf());
+    let mut drift = Vec::new();
+    for hook in GIT_HOOKS {
+        let link = dir.join(hook);
+        if !link.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            drift.push(format!("{} is not a symlink to {}", link.display(), binary.display()));
+        } else if !fs::canonicalize(&link).is_ok_and(|target| target == canonical_binary) {
+            let target = fs::read_link(&link)
+                .map(|t| t.display().to_string())
+                .unwrap_or_default();
+            drift.push(format!(
+                "{} points to {target}, expected {}",
+                link.display(),
+                binary.display()
+            ));
+        }
+    }
+    drift
+}
+
+/// What `prune_hook_symlinks` did to one hooks-dir entry.
+#[derive(Debug, PartialEq)]
+pub enum Pruned {
+    /// A hook symlink pointing elsewhere (or nowhere) now points at the binary.
+    Repointed(String),
+    /// A symlink for a name that isn't a known git hook was removed.
+    Removed(String),
+}
+
+/// Repoint hook symlinks in `dir` that are broken or resolve to something other
+/// than `binary`, and remove symlinks for names not in `GIT_HOOKS`.
+pub fn prune_hook_symlinks(dir: &Path, binary: &Path) -> Result<Vec<Pruned>, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {e}", dir.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    let canonical_binary = fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf());
+    let mut pruned = Vec::new();
+    for path in paths {
+        if !path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            continue;
+        }
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        if !is_hook_name(&name) {
+            fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {e}", path.display()))?;
+            pruned.push(Pruned::Removed(name));
+            continue;
+        }
+        if fs::canonicalize(&path).is_ok_and(|target| target == canonical_binary) {
+            continue;
+        }
+        fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {e}", path.display()))?;
+        symlink(binary, &path).map_err(|e| format!("failed to symlink {}: {e}", path.display()))?;
+        pruned.push(Pruned::Repointed(name));
+    }
+    Ok(pruned)
+}
+
+/// Whether the symlink `link` points at an lhm binary: `binary` itself, or a
+/// file with the same name, which covers an older or moved install whose
+/// target may no longer exist.
+fn links_to_lhm(link: &Path, binary: &Path) -> bool {
+    let Ok(target) = fs::read_link(link) else {
+        return false;
+    };
+    let canonical_binary = fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf());
+    fs::canonicalize(link).is_ok_and(|t| t == canonical_binary)
+        || (target.file_name().is_some() && target.file_name() == binary.file_name())
+}
+
+/// The `GIT_HOOKS` symlinks in `dir` that point at an lhm binary (see
+/// `links_to_lhm`), in `GIT_HOOKS` order. A missing `dir` has none.
+pub fn lhm_hook_symlinks(dir: &Path, binary: &Path) -> Vec<PathBuf> {
+    GIT_HOOKS
+        .iter()
+        .map(|hook| dir.join(hook))
+        .filter(|link| links_to_lhm(link, binary))
+        .collect()
+}
+
+/// Remove the hook symlinks in `dir` that point at an lhm binary, leaving
+/// other files and symlinks alone. Returns how many were removed.
+pub fn remove_hook_symlinks(dir: &Path, binary: &Path) -> Result<usize, String> {
+    let links = lhm_hook_symlinks(dir, binary);
+    for link in &links {
+        fs::remove_file(link).map_err(|e| format!("failed to remove {}: {e}", link.display()))?;
+    }
+    Ok(links.len())
+}
+
 /// Remove any entries in the hooks dir that aren't in the current `GIT_HOOKS` list.
 fn remove_stale_hooks(dir: &Path) {
     let entries = match fs::read_dir(dir) {
@@ -66,6 +232,106 @@ fn remove_stale_hooks(dir: &Path) {
     }
 }
 
+/// Marker key on adapter-generated commands, scripts and jobs naming where
+/// they came from, shown by `dry-run` and stripped before the config reaches
+/// lefthook.
+pub const LHM_SOURCE_KEY: &str = "lhm_source";
+
+/// Task key asking lhm to retry a failing `run`; see `apply_retries`.
+pub const LHM_RETRY_KEY: &str = "lhm_retry";
+
+/// Set `lhm_source: <source>` on every command, script and job in `config`,
+/// so tasks merged in from other layers stay unlabelled.
+pub fn mark_source(config: Value, source: &str) -> Value {
+    let Value::Mapping(mut root) = config else {
+        return config;
+    };
+    for (key, val) in &mut root {
+        if let (Some(name), Value::Mapping(hook_map)) = (key.as_str(), val)
+            && is_hook_name(name)
+        {
+            for task in hook_tasks(hook_map) {
+                task.insert(LHM_SOURCE_KEY.into(), source.into());
+            }
+        }
+    }
+    Value::Mapping(root)
+}
+
+/// Remove the `lhm_source` markers added by `mark_source`.
+pub fn strip_sources(config: Value) -> Value {
+    let Value::Mapping(mut root) = config else {
+        return config;
+    };
+    for (_, val) in &mut root {
+        if let Value::Mapping(hook_map) = val {
+            for task in hook_tasks(hook_map) {
+                task.remove(LHM_SOURCE_KEY);
+            }
+        }
+    }
+    Value::Mapping(root)
+}
+
+/// The command, script and top-level job mappings of a hook.
+fn hook_tasks(hook_map: &mut serde_yaml::Mapping) -> impl Iterator<Item = &mut serde_yaml::Mapping> {
+    let mut tasks = Vec::new();
+    for (key, section) in hook_map.iter_mut() {
+        match (key.as_str(), section) {
+            (Some("commands" | "scripts"), Value::Mapping(entries)) => tasks.extend(entries.values_mut()),
+            (Some("jobs"), Value::Sequence(jobs)) => tasks.extend(jobs.iter_mut()),
+            _ => {}
+        }
+    }
+    tasks.into_iter().filter_map(Value::as_mapping_mut)
+}
+
+/// Add each filter's patterns to the matching command in every hook as
+/// lefthook `only`/`skip` entries of the form `{ref: <pattern>}`. A scalar
+/// `only`/`skip` already on the command is kept as the first list entry, and
+/// `skip: true` is left alone since the command never runs anyway.
+pub fn apply_ref_filters(config: Value, filters: &[RefFilter]) -> Value {
+    let Value::Mapping(mut root) = config else {
+        return config;
+    };
+    for (key, val) in &mut root {
+        if !key.as_str().is_some_and(is_hook_name) {
+            continue;
+        }
+        let Some(Value::Mapping(commands)) = val.get_mut("commands") else {
+            continue;
+        };
+        for filter in filters {
+            if let Some(Value::Mapping(cmd)) = commands.get_mut(filter.command.as_str()) {
+                add_ref_conditions(cmd, "only", &filter.only);
+                add_ref_conditions(cmd, "skip", &filter.skip);
+            }
+        }
+    }
+    Value::Mapping(root)
+}
+
+fn add_ref_conditions(cmd: &mut serde_yaml::Mapping, key: &str, patterns: &[String]) {
+    if patterns.is_empty() {
+        return;
+    }
+    let mut conditions = match cmd.remove(key) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Bool(true)) if key == "skip" => {
+            cmd.insert(key.into(), Value::Bool(true));
+            return;
+        }
+        Some(Value::Sequence(seq)) => seq,
+        Some(other) => vec![other],
+    };
+    for pattern in patterns {
+        let mut condition = serde_yaml::Mapping::new();
+        condition.insert("ref".into(), pattern.as_str().into());
+        conditions.push(Value::Mapping(condition));
+    }
+    cmd.insert(key.into(), Value::Sequence(conditions));
+}
+
 /// Annotate adapter-generated config with lefthook settings:
 /// - `parallel: true` on hooks that don't mutate shared state
 /// - `stage_fixed: true` on each command within `pre-commit` and `pre-merge-commit` hooks
@@ -77,29 +343,176 @@ pub fn annotate_hooks(config: Value) -> Value {
         if let (Some(name), Value::Mapping(hook_map)) = (key.as_str(), val)
             && is_hook_name(name)
         {
-            if !SERIAL_HOOKS.contains(&name) {
+            // lefthook rejects `parallel` together with `piped`.
+            let piped = hook_map.get("piped").and_then(Value::as_bool) == Some(true);
+            if !SERIAL_HOOKS.contains(&name) && !piped {
                 hook_map.insert(Value::String("parallel".to_string()), Value::Bool(true));
+                trace::emit(json!({"event": "annotate", "hook": name, "annotation": "parallel"}));
             }
             if name == "pre-commit" || name == "pre-merge-commit" {
                 set_stage_fixed(hook_map);
+                trace::emit(json!({"event": "annotate", "hook": name, "annotation": "stage_fixed"}));
             }
         }
     }
     Value::Mapping(root)
 }
 
-/// Add `stage_fixed: true` to every command in a hook mapping.
+/// Tag marking a command that never modifies files, so it gets no `stage_fixed`.
+const READONLY_TAG: &str = "readonly";
+
+/// Add `stage_fixed: true` to every command in a hook mapping, except ones
+/// that set `stage_fixed` themselves or are tagged `readonly`.
 fn set_stage_fixed(hook_map: &mut serde_yaml::Mapping) {
     let commands_key = Value::String("commands".to_string());
     if let Some(Value::Mapping(commands)) = hook_map.get_mut(&commands_key) {
         for (_cmd_name, cmd_val) in commands.iter_mut() {
-            if let Value::Mapping(cmd_map) = cmd_val {
+            if let Value::Mapping(cmd_map) = cmd_val
+                && !cmd_map.contains_key("stage_fixed")
+                && !has_tag(cmd_map, READONLY_TAG)
+            {
                 cmd_map.insert(Value::String("stage_fixed".to_string()), Value::Bool(true));
             }
         }
     }
 }
 
+/// Whether a command's `tags` (a list, or a space-separated string) include `tag`.
+fn has_tag(cmd_map: &serde_yaml::Mapping, tag: &str) -> bool {
+    match cmd_map.get("tags") {
+        Some(Value::Sequence(tags)) => tags.iter().any(|t| t.as_str() == Some(tag)),
+        Some(Value::String(tags)) => tags.split_whitespace().any(|t| t == tag),
+        _ => false,
+    }
+}
+
+/// Apply the `lhm.jobs: N` concurrency limit. lefthook has no job limit, only
+/// `parallel` on or off, so `1` turns `parallel` off on every hook and larger
+/// values are ignored with a warning. The `lhm` key itself is left for
+/// `strip_lhm_settings`.
+pub fn apply_jobs_limit(config: Value) -> Value {
+    let Some(jobs) = config.get(LHM_SETTINGS_KEY).and_then(|lhm| lhm.get("jobs")).cloned() else {
+        return config;
+    };
+    match jobs.as_u64() {
+        Some(1) => {}
+        Some(n) if n > 1 => {
+            warn!("lhm.jobs: {n} has no lefthook equivalent; parallel hooks run all their commands at once");
+            return config;
+        }
+        _ => {
+            warn!("ignoring lhm.jobs: {jobs:?} (expected a positive integer)");
+            return config;
+        }
+    }
+    let Value::Mapping(mut root) = config else {
+        return config;
+    };
+    for (key, val) in &mut root {
+        if let (Some(name), Value::Mapping(hook_map)) = (key.as_str(), val)
+            && is_hook_name(name)
+            && hook_map.remove("parallel").is_some()
+        {
+            trace::emit(json!({"event": "jobs_limit", "hook": name}));
+        }
+    }
+    Value::Mapping(root)
+}
+
+/// Apply the `lhm.output` entry (a map of hook name to lefthook `output`
+/// value) for `hook_name`, the hook being run, as the config's top-level
+/// `output`. lefthook only reads `output` at the top level, but lhm runs it
+/// for one hook at a time, so that is where a per-hook value belongs. Without
+/// a hook (e.g. a `dry-run` of every hook) or an entry for it, the config is
+/// left alone.
+pub fn apply_hook_output(config: Value, hook_name: Option<&str>) -> Value {
+    let Some(outputs) = config.get(LHM_SETTINGS_KEY).and_then(|lhm| lhm.get("output")).cloned() else {
+        return config;
+    };
+    let Value::Mapping(outputs) = outputs else {
+        warn!("ignoring lhm.output: expected a map of hook name to output setting");
+        return config;
+    };
+    for hook in outputs.keys() {
+        if !hook.as_str().is_some_and(is_hook_name) {
+            warn!("ignoring lhm.output for {hook:?}: not a git hook");
+        }
+    }
+    let (Some(name), Value::Mapping(mut root)) = (hook_name, config.clone()) else {
+        return config;
+    };
+    let Some(output) = outputs.get(name) else {
+        return config;
+    };
+    root.insert("output".into(), output.clone());
+    trace::emit(json!({"event": "annotate", "hook": name, "annotation": "output"}));
+    Value::Mapping(root)
+}
+
+/// Rewrite commands and jobs carrying `lhm_retry: N` so their `run` is retried
+/// up to `N` times on failure. The `lhm_retry` key is always stripped since
+/// lefthook doesn't know it.
+pub fn apply_retries(config: Value) -> Value {
+    let Value::Mapping(mut root) = config else {
+        return config;
+    };
+    for (key, val) in &mut root {
+        if let (Some(name), Value::Mapping(hook_map)) = (key.as_str(), val)
+            && is_hook_name(name)
+        {
+            if let Some(Value::Mapping(commands)) = hook_map.get_mut("commands") {
+                for (_cmd_name, cmd_val) in commands.iter_mut() {
+                    if let Value::Mapping(cmd_map) = cmd_val {
+                        apply_retry(cmd_map);
+                    }
+                }
+            }
+            if let Some(jobs) = hook_map.get_mut("jobs") {
+                apply_job_retries(jobs);
+            }
+        }
+    }
+    Value::Mapping(root)
+}
+
+/// `apply_retry` on every job, including those nested in `group.jobs`.
+fn apply_job_retries(jobs: &mut Value) {
+    let Value::Sequence(jobs) = jobs else {
+        return;
+    };
+    for job in jobs.iter_mut() {
+        if let Value::Mapping(job_map) = job {
+            apply_retry(job_map);
+            if let Some(Value::Mapping(group)) = job_map.get_mut("group")
+                && let Some(nested) = group.get_mut("jobs")
+            {
+                apply_job_retries(nested);
+            }
+        }
+    }
+}
+
+fn apply_retry(task: &mut serde_yaml::Mapping) {
+    let Some(retry) = task.remove(LHM_RETRY_KEY) else {
+        return;
+    };
+    let Some(retries) = retry.as_u64().filter(|n| *n > 0) else {
+        debug!("ignoring lhm_retry: {retry:?}");
+        return;
+    };
+    if let Some(Value::String(run)) = task.get_mut("run") {
+        *run = retry_wrapper(run, retries);
+    }
+}
+
+/// Wrap a shell command in a loop that retries it up to `retries` times.
+fn retry_wrapper(run: &str, retries: u64) -> String {
+    format!(
+        "i=0; until ( {run} ); do i=$((i+1)); if [ \"$i\" -gt {retries} ]; then exit 1; fi; \
+         echo \"lhm: retrying ($i/{retries})\" >&2; done"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +526,37 @@ mod tests {
         serde_yaml::to_string(v).unwrap()
     }
 
+    #[test]
+    fn test_apply_ref_filters() {
+        let config: Value = serde_yaml::from_str(
+            "pre-push:\n  commands:\n    test:\n      run: cargo test\n    e2e:\n      run: e2e\n      skip: merge\n    lint:\n      run: lint\n      skip: true\n",
+        )
+        .unwrap();
+        let filters = [
+            RefFilter {
+                command: "test".into(),
+                only: vec!["main".into(), "release/*".into()],
+                skip: vec![],
+            },
+            RefFilter {
+                command: "e2e".into(),
+                only: vec![],
+                skip: vec!["feature/*".into()],
+            },
+            RefFilter {
+                command: "lint".into(),
+                only: vec![],
+                skip: vec!["wip".into()],
+            },
+        ];
+        let out = apply_ref_filters(config, &filters);
+        let expected: Value = serde_yaml::from_str(
+            "pre-push:\n  commands:\n    test:\n      run: cargo test\n      only:\n        - ref: main\n        - ref: release/*\n    e2e:\n      run: e2e\n      skip:\n        - merge\n        - ref: feature/*\n    lint:\n      run: lint\n      skip: true\n",
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
     #[test]
     fn test_is_hook_name() {
         assert!(is_hook_name("pre-commit"));
@@ -140,6 +584,157 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hook_symlink_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "new").unwrap();
+        assert_eq!(hook_symlink_drift(&hooks, &binary).len(), GIT_HOOKS.len());
+
+        create_hook_symlinks(&hooks, &binary).unwrap();
+        assert!(hook_symlink_drift(&hooks, &binary).is_empty());
+
+        let old_binary = dir.path().join("old-lhm");
+        fs::remove_file(hooks.join("pre-push")).unwrap();
+        symlink(&old_binary, hooks.join("pre-push")).unwrap();
+        fs::remove_file(hooks.join("commit-msg")).unwrap();
+        let drift = hook_symlink_drift(&hooks, &binary);
+        assert_eq!(drift.len(), 2, "{drift:?}");
+        assert!(drift[0].contains("commit-msg is not a symlink"), "{drift:?}");
+        assert!(
+            drift[1].ends_with(&format!(
+                "pre-push points to {}, expected {}",
+                old_binary.display(),
+                binary.display()
+            )),
+            "{drift:?}"
+        );
+    }
+
+    #[test]
+    fn test_prune_hook_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let old_binary = dir.path().join("old-lhm");
+        let binary = dir.path().join("lhm");
+        fs::write(&old_binary, "old").unwrap();
+        fs::write(&binary, "new").unwrap();
+
+        create_hook_symlinks(&hooks, &binary).unwrap();
+        fs::remove_file(hooks.join("pre-push")).unwrap();
+        symlink(&old_binary, hooks.join("pre-push")).unwrap();
+        fs::remove_file(hooks.join("commit-msg")).unwrap();
+        symlink(dir.path().join("gone"), hooks.join("commit-msg")).unwrap();
+        symlink(&binary, hooks.join("pre-receive-old")).unwrap();
+
+        let pruned = prune_hook_symlinks(&hooks, &binary).unwrap();
+        assert_eq!(
+            pruned,
+            [
+                Pruned::Repointed("commit-msg".to_string()),
+                Pruned::Repointed("pre-push".to_string()),
+                Pruned::Removed("pre-receive-old".to_string()),
+            ]
+        );
+        assert_eq!(fs::read_link(hooks.join("pre-push")).unwrap(), binary);
+        assert_eq!(fs::read_link(hooks.join("commit-msg")).unwrap(), binary);
+        assert!(!hooks.join("pre-receive-old").exists());
+        assert_eq!(hook_symlink_status(&hooks, &binary).current, GIT_HOOKS.len());
+
+        assert!(prune_hook_symlinks(&hooks, &binary).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_remove_hook_symlinks_then_reinstall() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        fs::create_dir_all(dir.path().join("old")).unwrap();
+        let old_binary = dir.path().join("old").join("lhm");
+        let binary = dir.path().join("lhm");
+        fs::write(&old_binary, "old").unwrap();
+        fs::write(&binary, "new").unwrap();
+
+        create_hook_symlinks(&hooks, &old_binary).unwrap();
+        symlink(&old_binary, hooks.join("pre-receive-old")).unwrap();
+        fs::write(hooks.join("README"), "not a hook").unwrap();
+
+        // the old binary has lhm's name, so its links count even once it's gone
+        fs::remove_file(&old_binary).unwrap();
+        assert_eq!(lhm_hook_symlinks(&hooks, &binary).len(), GIT_HOOKS.len());
+        assert_eq!(remove_hook_symlinks(&hooks, &binary).unwrap(), GIT_HOOKS.len());
+        assert_eq!(hook_symlink_status(&hooks, &old_binary).present, 0);
+        assert!(hooks.join("README").exists());
+        assert!(hooks.join("pre-receive-old").symlink_metadata().is_ok());
+
+        create_hook_symlinks(&hooks, &binary).unwrap();
+        assert!(hook_symlink_drift(&hooks, &binary).is_empty());
+        for hook in GIT_HOOKS {
+            assert_eq!(
+                fs::canonicalize(hooks.join(hook)).unwrap(),
+                fs::canonicalize(&binary).unwrap()
+            );
+        }
+
+        assert_eq!(remove_hook_symlinks(&dir.path().join("missing"), &binary).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_remove_hook_symlinks_keeps_user_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("githooks");
+        let binary = dir.path().join("lhm");
+        let script = dir.path().join("check.sh");
+        fs::write(&binary, "lhm").unwrap();
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::create_dir_all(&hooks).unwrap();
+        symlink(&script, hooks.join("pre-commit")).unwrap();
+        symlink(&binary, hooks.join("pre-push")).unwrap();
+
+        assert_eq!(lhm_hook_symlinks(&hooks, &binary), [hooks.join("pre-push")]);
+        assert_eq!(remove_hook_symlinks(&hooks, &binary).unwrap(), 1);
+        assert_eq!(fs::read_link(hooks.join("pre-commit")).unwrap(), script);
+    }
+
+    #[test]
+    fn test_link_git_dir_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join(".git/hooks");
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "fake").unwrap();
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("pre-commit.sample"), "sample").unwrap();
+        fs::write(hooks.join("pre-push"), "#!/bin/sh\n").unwrap();
+
+        let err = link_git_dir_hooks(&hooks, &binary, false).unwrap_err();
+        assert!(
+            err.ends_with("already has hook scripts: pre-push (use --force to replace them)"),
+            "{err}"
+        );
+        assert_eq!(hook_symlink_status(&hooks, &binary).present, 0);
+
+        link_git_dir_hooks(&hooks, &binary, true).unwrap();
+        assert!(hook_symlink_drift(&hooks, &binary).is_empty());
+        assert!(hooks.join("pre-commit.sample").exists());
+
+        // Relinking over our own symlinks needs no force
+        link_git_dir_hooks(&hooks, &binary, false).unwrap();
+    }
+
+    #[test]
+    fn test_validate_hooks_dir_creates_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("custom").join("hooks");
+        validate_hooks_dir(&hooks).unwrap();
+        assert!(hooks.is_dir());
+    }
+
+    #[test]
+    fn test_validate_hooks_dir_rejects_relative() {
+        let err = validate_hooks_dir(Path::new("relative/hooks")).unwrap_err();
+        assert!(err.contains("absolute"), "{err}");
+    }
+
     #[test]
     fn test_create_hook_symlinks_overwrites_existing() {
         let dir = tempfile::tempdir().unwrap();
@@ -184,6 +779,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hook_symlink_status_all_current() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let fake_binary = dir.path().join("lhm");
+        fs::write(&fake_binary, "fake").unwrap();
+        create_hook_symlinks(&hooks, &fake_binary).unwrap();
+
+        let status = hook_symlink_status(&hooks, &fake_binary);
+        assert_eq!(
+            status,
+            SymlinkStatus {
+                present: GIT_HOOKS.len(),
+                current: GIT_HOOKS.len()
+            }
+        );
+    }
+
+    #[test]
+    fn test_hook_symlink_status_stale_and_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        let old_binary = dir.path().join("old-lhm");
+        let new_binary = dir.path().join("lhm");
+        fs::write(&old_binary, "old").unwrap();
+        fs::write(&new_binary, "new").unwrap();
+
+        symlink(&new_binary, hooks.join("pre-commit")).unwrap();
+        symlink(&old_binary, hooks.join("pre-push")).unwrap();
+        symlink(dir.path().join("gone"), hooks.join("commit-msg")).unwrap();
+        fs::write(hooks.join("post-merge"), "regular file").unwrap();
+
+        let status = hook_symlink_status(&hooks, &new_binary);
+        assert_eq!(status, SymlinkStatus { present: 3, current: 1 });
+    }
+
     #[test]
     fn test_annotate_hooks_parallel_on_safe_hooks() {
         let config = yaml("pre-push:\n  commands:\n    foo:\n      run: echo hi\noutput:\n  - success\n");
@@ -203,6 +835,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_annotate_hooks_no_parallel_on_piped_hooks() {
+        let config = yaml("pre-push:\n  piped: true\n  commands:\n    test:\n      run: t\n");
+        let result = annotate_hooks(config);
+        assert!(result["pre-push"].get("parallel").is_none());
+    }
+
     #[test]
     fn test_annotate_hooks_stage_fixed_on_pre_commit_hooks() {
         for hook in &["pre-commit", "pre-merge-commit"] {
@@ -224,6 +863,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_annotate_hooks_stage_fixed_selective() {
+        let config = yaml(
+            "pre-commit:\n  commands:\n    fmt:\n      run: fmt\n    check:\n      run: check\n      tags: [readonly, lint]\n    audit:\n      run: audit\n      tags: security readonly\n    gen:\n      run: gen\n      stage_fixed: false\n",
+        );
+        let result = annotate_hooks(config);
+        let commands = &result["pre-commit"]["commands"];
+        assert_eq!(commands["fmt"]["stage_fixed"], Value::Bool(true));
+        assert!(commands["check"].get("stage_fixed").is_none(), "{commands:?}");
+        assert!(commands["audit"].get("stage_fixed").is_none(), "{commands:?}");
+        assert_eq!(
+            commands["gen"]["stage_fixed"],
+            Value::Bool(false),
+            "explicit value kept"
+        );
+    }
+
     #[test]
     fn test_annotate_hooks_no_stage_fixed_on_pre_push() {
         let config = yaml("pre-push:\n  commands:\n    foo:\n      run: echo hi\n");
@@ -232,6 +888,149 @@ mod tests {
         assert!(!out.contains("stage_fixed"), "no stage_fixed on pre-push: {out}");
     }
 
+    #[test]
+    fn test_mark_and_strip_sources() {
+        let config = yaml("skip_lfs: true\npre-push:\n  commands:\n    a:\n      run: a\n");
+        let marked = mark_source(config.clone(), "husky-adapter");
+        assert_eq!(
+            marked["pre-push"]["commands"]["a"]["lhm_source"].as_str(),
+            Some("husky-adapter")
+        );
+        assert!(marked["pre-push"].get("lhm_source").is_none());
+        assert_eq!(strip_sources(marked), config);
+
+        let config = yaml("pre-push:\n  jobs:\n    - run: a\n  scripts:\n    b.sh:\n      runner: sh\n");
+        let marked = mark_source(config.clone(), "pre-commit-adapter");
+        assert_eq!(
+            marked["pre-push"]["jobs"][0]["lhm_source"].as_str(),
+            Some("pre-commit-adapter")
+        );
+        assert_eq!(
+            marked["pre-push"]["scripts"]["b.sh"]["lhm_source"].as_str(),
+            Some("pre-commit-adapter")
+        );
+        assert_eq!(strip_sources(marked), config);
+    }
+
+    #[test]
+    fn test_apply_jobs_limit_one_disables_parallel() {
+        let config = yaml("lhm:\n  jobs: 1\npre-push:\n  parallel: true\n  commands:\n    a:\n      run: a\n");
+        let result = apply_jobs_limit(config);
+        assert!(result["pre-push"].get("parallel").is_none(), "{result:?}");
+    }
+
+    #[test]
+    fn test_apply_jobs_limit_unsupported_values_keep_parallel() {
+        for jobs in ["4", "0", "many"] {
+            let config = yaml(&format!("lhm:\n  jobs: {jobs}\npre-push:\n  parallel: true\n"));
+            let result = apply_jobs_limit(config);
+            assert_eq!(result["pre-push"]["parallel"], Value::Bool(true), "jobs: {jobs}");
+        }
+    }
+
+    #[test]
+    fn test_apply_hook_output() {
+        let config = yaml(
+            "output: [failure]\nlhm:\n  output:\n    pre-push: [meta, summary, execution, failure]\n    pre-commit: false\n    lint: false\npre-push:\n  commands:\n    test:\n      run: just test\npre-commit:\n  commands:\n    fmt:\n      run: fmt\n",
+        );
+        let push = apply_hook_output(config.clone(), Some("pre-push"));
+        assert_eq!(push["output"], yaml("[meta, summary, execution, failure]"));
+        assert!(
+            push["pre-push"].get("output").is_none(),
+            "lefthook has no hook-level output"
+        );
+
+        let commit = apply_hook_output(config.clone(), Some("pre-commit"));
+        assert_eq!(commit["output"], Value::Bool(false));
+
+        // hooks without an entry keep the top-level output
+        assert_eq!(apply_hook_output(config.clone(), Some("commit-msg")), config);
+        assert_eq!(apply_hook_output(config.clone(), None), config);
+    }
+
+    #[test]
+    fn test_apply_hook_output_unset_leaves_config_alone() {
+        let config = yaml("lhm:\n  jobs: 1\npre-push:\n  commands:\n    test:\n      run: just test\n");
+        assert_eq!(apply_hook_output(config.clone(), Some("pre-push")), config);
+    }
+
+    #[test]
+    fn test_apply_retries_rewrites_run() {
+        let config = yaml("pre-push:\n  commands:\n    test:\n      run: just test\n      lhm_retry: 2\n");
+        let result = apply_retries(config);
+        let cmd = &result["pre-push"]["commands"]["test"];
+        assert_eq!(cmd["run"].as_str(), Some(retry_wrapper("just test", 2).as_str()));
+        assert!(cmd.get("lhm_retry").is_none(), "lhm_retry stripped: {cmd:?}");
+    }
+
+    #[test]
+    fn test_apply_retries_on_jobs() {
+        let config = yaml("pre-push:\n  jobs:\n    - name: test\n      run: just test\n      lhm_retry: 3\n");
+        let result = apply_retries(config);
+        let out = to_yaml(&result);
+        assert!(out.contains("until ( just test )"), "wrapped: {out}");
+        assert!(!out.contains("lhm_retry"), "stripped: {out}");
+    }
+
+    #[test]
+    fn test_apply_retries_on_nested_group_jobs() {
+        let config = yaml(
+            "pre-push:\n  jobs:\n    - name: checks\n      group:\n        jobs:\n          - run: just test\n            lhm_retry: 2\n",
+        );
+        let result = apply_retries(config);
+        let job = &result["pre-push"]["jobs"][0]["group"]["jobs"][0];
+        assert_eq!(job["run"].as_str(), Some(retry_wrapper("just test", 2).as_str()));
+        assert!(job.get("lhm_retry").is_none(), "lhm_retry stripped: {job:?}");
+    }
+
+    #[test]
+    fn test_apply_retries_absent_leaves_run() {
+        let config = yaml("pre-push:\n  commands:\n    test:\n      run: just test\n");
+        let result = apply_retries(config);
+        assert_eq!(
+            result["pre-push"]["commands"]["test"]["run"].as_str(),
+            Some("just test")
+        );
+    }
+
+    #[test]
+    fn test_apply_retries_zero_strips_key_only() {
+        let config = yaml("pre-push:\n  commands:\n    test:\n      run: just test\n      lhm_retry: 0\n");
+        let result = apply_retries(config);
+        let cmd = &result["pre-push"]["commands"]["test"];
+        assert_eq!(cmd["run"].as_str(), Some("just test"));
+        assert!(cmd.get("lhm_retry").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_retry_wrapper_retries_until_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("count");
+        // Fails on the first two attempts, succeeds on the third
+        let run = format!("echo x >> {0}; [ $(wc -l < {0}) -ge 3 ]", counter.display());
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(retry_wrapper(&run, 2))
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_retry_wrapper_gives_up() {
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(retry_wrapper("false", 1))
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!status.success());
+    }
+
     #[test]
     fn test_annotate_hooks_skips_non_hook_keys() {
         let config = yaml("output:\n  - success\n");
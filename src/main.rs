@@ -1,29 +1,49 @@
 mod adapters;
 mod config;
+mod doctor;
 mod hooks;
 mod merge;
+mod process;
+mod schema;
+mod style;
+mod trace;
+mod validate;
 
 use clap::{Parser, Subcommand};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde_yaml::Value;
 use std::env;
 use std::path::{Path, PathBuf};
-use std::process::{Command, ExitCode, Stdio};
+use std::process::{Command, ExitCode, ExitStatus, Stdio};
 
 use config::{
-    ConfigOverrides, install_default_global_config, load_global_config, read_yaml, repo_config, write_merged_temp,
+    ConfigOverrides, ConfigProfile, REPO_SETTINGS_FILE, STDIN_NAME, config_path_report, disabled_adapters, edit_path,
+    env_flag, find_config, install_default_global_config, is_ci, lhm_flag, load_global_config, load_repo_config,
+    merge_nested_configs, merge_strategy, merged_yaml, pinned_adapter, read_config_from, read_yaml, ref_filters,
+    repo_config, strip_lhm_settings, tolerate_load_error, write_atomic, write_merged_temp,
 };
-use hooks::{GIT_HOOKS, annotate_hooks, create_hook_symlinks, is_hook_name};
-use merge::merge_configs;
+use hooks::{
+    GIT_HOOKS, Pruned, SymlinkStatus, annotate_hooks, apply_hook_output, apply_jobs_limit, apply_ref_filters,
+    apply_retries, create_hook_symlinks, hook_symlink_drift, hook_symlink_status, is_hook_name, lhm_hook_symlinks,
+    link_git_dir_hooks, mark_source, prune_hook_symlinks, remove_hook_symlinks, strip_sources, validate_hooks_dir,
+};
+use merge::merge_configs_checked;
 
-fn init_logger(cli_debug: bool) {
-    let debug_enabled = cli_debug || env::var("LHM_DEBUG").is_ok_and(|v| v == "1" || v == "true");
+/// Pick the log level from the verbosity count and quiet flag.
+/// Verbosity wins over quiet: `-v` is debug, `-vv` and above is trace.
+fn log_level(verbose: u8, quiet: bool) -> log::LevelFilter {
+    match verbose {
+        0 if quiet => log::LevelFilter::Warn,
+        0 => log::LevelFilter::Info,
+        1 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
 
-    let level = if debug_enabled {
-        log::LevelFilter::Debug
-    } else {
-        log::LevelFilter::Info
-    };
+fn init_logger(cli_verbose: u8, cli_quiet: bool) {
+    let verbose = cli_verbose.max(u8::from(env_flag("LHM_DEBUG")));
+    let quiet = cli_quiet || env_flag("LHM_QUIET");
+    let level = log_level(verbose, quiet);
 
     env_logger::Builder::new()
         .filter_level(level)
@@ -62,6 +82,14 @@ struct Cli {
     #[arg(long, global = true)]
     debug: bool,
 
+    /// Increase log verbosity (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log warnings and errors (also via LHM_QUIET=1)
+    #[arg(short, long, global = true, conflicts_with_all = ["debug", "verbose"])]
+    quiet: bool,
+
     /// Path to the global lefthook config (also via LHM_GLOBAL_CONFIG)
     #[arg(long, global = true)]
     global_config: Option<PathBuf>,
@@ -76,31 +104,179 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Configure global core.hooksPath to use lhm
-    Install,
+    /// Configure core.hooksPath (global unless --local) to use lhm
+    Install {
+        /// Write the default global config here instead of ~/.lefthook.yaml
+        #[arg(long)]
+        config_path: Option<PathBuf>,
+        /// Create hook symlinks here instead of ~/.lhm/hooks (must be absolute)
+        #[arg(long)]
+        hooks_dir: Option<PathBuf>,
+        /// Overwrite a core.hooksPath that points at another tool
+        #[arg(long)]
+        force: bool,
+        /// Set core.hooksPath for the current repository only
+        #[arg(long)]
+        local: bool,
+        /// Report differences from what install would set up, without changing anything
+        #[arg(long)]
+        check: bool,
+        /// Point hook symlinks at this lhm binary instead of the running one (must be absolute)
+        #[arg(long)]
+        binary: Option<PathBuf>,
+        /// Template for the default global config, if one gets written
+        #[arg(long, value_enum, default_value_t)]
+        profile: ConfigProfile,
+    },
+    /// Print the paths of the effective global and repo configs
+    ConfigPath,
+    /// Print the versions of lhm, lefthook and git
+    Version,
     /// Print the merged config that would be used, then exit
-    DryRun,
+    DryRun {
+        /// Skip the automatic parallel/stage_fixed annotations on adapter config
+        #[arg(long)]
+        no_annotate: bool,
+        /// Exit non-zero if the merged config defines no hooks
+        #[arg(long)]
+        require_hooks: bool,
+        /// Print only this adapter's output for every hook, without merging
+        #[arg(long, value_name = "ADAPTER")]
+        select: Option<String>,
+        /// Process a config read from stdin instead of the global and repo configs
+        #[arg(long, conflicts_with = "select")]
+        stdin: bool,
+        /// Also print the adapter's own output, even when a repo config keeps it from being used
+        #[arg(long, conflicts_with_all = ["select", "stdin"])]
+        all: bool,
+    },
     /// Remove global core.hooksPath, disabling lhm
     Disable,
+    /// Check that git, lefthook and the lhm hooks are set up correctly
+    Doctor {
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the current core.hooksPath settings and hook symlink state
+    Status {
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Open the active global (or repo) config in $VISUAL / $EDITOR
+    Edit {
+        /// Edit the repo config instead of the global one
+        #[arg(long)]
+        repo: bool,
+    },
+    /// Check a single lefthook config file for structural problems
+    Validate {
+        /// Config file to check (`-` reads it from stdin)
+        path: PathBuf,
+    },
+    /// Repoint hook symlinks that don't resolve to this lhm binary
+    Prune,
+    /// Remove the existing hook symlinks, then install again for this binary
+    Reinstall {
+        /// Reinstall here instead of the current core.hooksPath (must be absolute)
+        #[arg(long)]
+        hooks_dir: Option<PathBuf>,
+        /// Reinstall core.hooksPath for the current repository only
+        #[arg(long)]
+        local: bool,
+        /// Point hook symlinks at this lhm binary instead of the one they point at now (must be absolute)
+        #[arg(long)]
+        binary: Option<PathBuf>,
+        /// Template for the default global config, if one gets written
+        #[arg(long, value_enum, default_value_t)]
+        profile: ConfigProfile,
+    },
+    /// Explain which adapter is used for the current repo and why
+    Explain,
+    /// Print the exact config a hook run would pass to lefthook, without running it
+    DebugMerged {
+        /// Git hook to merge the config for (e.g. pre-commit)
+        hook: String,
+    },
+    /// Print a JSON Schema for the lefthook config lhm understands
+    Schema,
+    /// Write lefthook.yaml in the repo root from the detected adapter
+    Migrate {
+        /// Overwrite an existing repo config
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 fn main() -> ExitCode {
     let invoked_as = invoked_name();
 
     if is_hook_name(&invoked_as) {
-        init_logger(false);
+        init_logger(0, false);
         debug!("invoked as hook: {invoked_as}");
         let overrides = ConfigOverrides::from_env();
         return run_hook(&invoked_as, env::args().skip(1).collect(), &overrides);
     }
 
     let cli = Cli::parse();
-    init_logger(cli.debug);
+    init_logger(cli.verbose.max(u8::from(cli.debug)), cli.quiet);
     let overrides = ConfigOverrides::new(cli.global_config, cli.local_config);
     match cli.command {
-        Commands::Install => install(),
-        Commands::DryRun => dry_run(&overrides),
+        Commands::Install {
+            config_path,
+            hooks_dir,
+            force,
+            local,
+            check,
+            binary,
+            profile,
+        } => {
+            if check {
+                install_check(hooks_dir.as_deref(), local, binary.as_deref())
+            } else {
+                install(
+                    config_path.as_deref(),
+                    hooks_dir.as_deref(),
+                    force,
+                    local,
+                    binary.as_deref(),
+                    profile,
+                )
+            }
+        }
+        Commands::ConfigPath => config_path(&overrides),
+        Commands::Version => version(),
+        Commands::DryRun {
+            no_annotate,
+            require_hooks,
+            select,
+            stdin,
+            all,
+        } => {
+            let annotate = !no_annotate && !env_flag("LHM_NO_ANNOTATE");
+            match select {
+                Some(name) => dry_run_select(&name, annotate),
+                None if stdin => dry_run_stdin(annotate, require_hooks),
+                None => dry_run(&overrides, annotate, require_hooks, all),
+            }
+        }
         Commands::Disable => disable(),
+        Commands::Doctor { json } => doctor(&overrides, json),
+        Commands::Status { json } => status(json),
+        Commands::Edit { repo } => edit(repo, &overrides),
+        Commands::Validate { path } => validate(&path),
+        Commands::Prune => prune(),
+        Commands::Reinstall {
+            hooks_dir,
+            local,
+            binary,
+            profile,
+        } => reinstall(hooks_dir.as_deref(), local, binary.as_deref(), profile),
+        Commands::Explain => explain(&overrides),
+        Commands::DebugMerged { hook } => debug_merged(&hook, &overrides),
+        Commands::Schema => schema(),
+        Commands::Migrate { force } => migrate(force, &overrides),
     }
 }
 
@@ -123,22 +299,72 @@ fn hooks_dir() -> PathBuf {
 }
 
 fn repo_root() -> Option<PathBuf> {
-    Command::new("git")
+    repo_root_in(&env::current_dir().ok()?)
+}
+
+/// The work tree root containing `cwd`, canonicalized so that a repo reached
+/// through a symlink yields the same paths as one reached directly. Only the
+/// top-level directory itself is resolved; nothing under `.git` is touched.
+fn repo_root_in(cwd: &Path) -> Option<PathBuf> {
+    let root = Command::new("git")
         .args(["rev-parse", "--show-toplevel"])
+        .current_dir(cwd)
         .stderr(Stdio::null())
         .output()
         .ok()
         .filter(|o| o.status.success())
-        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()))
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()))?;
+    Some(std::fs::canonicalize(&root).unwrap_or(root))
 }
 
-fn install() -> ExitCode {
-    let dir = hooks_dir();
-    let binary = env::current_exe().expect("cannot determine lhm binary path");
+fn install(
+    config_path: Option<&Path>,
+    custom_dir: Option<&Path>,
+    force: bool,
+    local: bool,
+    custom_binary: Option<&Path>,
+    profile: ConfigProfile,
+) -> ExitCode {
+    let binary = match install_binary(custom_binary) {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let scope = match install_scope(local) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match install_mode(doctor::git_version(), local) {
+        Ok(InstallMode::HooksPath) => {}
+        Ok(InstallMode::GitHooksDir) => return install_git_hooks_dir(config_path, &binary, force, profile),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let current = doctor::git_config(scope, "core.hooksPath");
+    let target = custom_dir.map_or_else(hooks_dir, Path::to_path_buf);
+    if let Err(e) = check_existing_hooks_path(current.as_deref(), &target, &binary, force) {
+        error!("{e}");
+        return ExitCode::FAILURE;
+    }
+
+    let dir = match install_dir(custom_dir) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
     debug!("hooks dir: {}", dir.display());
     debug!("binary path: {}", binary.display());
 
-    if let Err(e) = install_default_global_config(&home_dir()) {
+    if let Err(e) = install_default_global_config(&home_dir(), config_path, profile) {
         error!("{e}");
         return ExitCode::FAILURE;
     }
@@ -148,15 +374,14 @@ fn install() -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    let status = Command::new("git")
-        .args(["config", "--global", "core.hooksPath"])
-        .arg(&dir)
-        .status();
-
-    match status {
+    match set_hooks_path_command(&dir, scope).status() {
         Ok(s) if s.success() => {
             info!("installed hooks to {}", dir.display());
-            info!("set core.hooksPath = {}", dir.display());
+            info!(
+                "set core.hooksPath = {} ({})",
+                dir.display(),
+                scope.trim_start_matches('-')
+            );
             ExitCode::SUCCESS
         }
         _ => {
@@ -166,220 +391,2433 @@ fn install() -> ExitCode {
     }
 }
 
-fn disable() -> ExitCode {
-    let status = Command::new("git")
-        .args(["config", "--global", "--unset", "core.hooksPath"])
-        .status();
+/// How `install` wires the hooks up.
+#[derive(Debug, PartialEq)]
+enum InstallMode {
+    /// Point `core.hooksPath` at the lhm hooks dir.
+    HooksPath,
+    /// Symlink the hooks into the repository's own `.git/hooks`.
+    GitHooksDir,
+}
 
-    match status {
-        Ok(s) if s.success() => {
-            info!("removed core.hooksPath, lhm disabled");
-            ExitCode::SUCCESS
-        }
-        Ok(s) if s.code() == Some(5) => {
-            // Exit code 5 means the key was not set
-            info!("core.hooksPath was not set, nothing to do");
+/// Git older than 2.9 ignores `core.hooksPath`, so a local install links into
+/// `.git/hooks` instead and a global install is refused. An unknown git
+/// version is assumed to be recent.
+fn install_mode(git_version: Option<(u32, u32)>, local: bool) -> Result<InstallMode, String> {
+    match git_version {
+        Some(v) if v < doctor::MIN_HOOKS_PATH_GIT && local => Ok(InstallMode::GitHooksDir),
+        Some((major, minor)) if (major, minor) < doctor::MIN_HOOKS_PATH_GIT => Err(format!(
+            "git {major}.{minor} doesn't support core.hooksPath (needs 2.9 or later), so a global install would never \
+             run; upgrade git or run `lhm install --local` in each repository"
+        )),
+        _ => Ok(InstallMode::HooksPath),
+    }
+}
+
+/// `install --local` on git without `core.hooksPath`: symlink the hooks into
+/// the current repository's `.git/hooks`.
+fn install_git_hooks_dir(config_path: Option<&Path>, binary: &Path, force: bool, profile: ConfigProfile) -> ExitCode {
+    let Some(root) = repo_root() else {
+        error!("install --local must be run inside a git repository");
+        return ExitCode::FAILURE;
+    };
+    let dir = git_hooks_dir(&root);
+    warn!(
+        "git doesn't support core.hooksPath, linking hooks into {} instead",
+        dir.display()
+    );
+    if let Err(e) = install_default_global_config(&home_dir(), config_path, profile) {
+        error!("{e}");
+        return ExitCode::FAILURE;
+    }
+    match link_git_dir_hooks(&dir, binary, force) {
+        Ok(()) => {
+            info!("installed hooks to {}", dir.display());
             ExitCode::SUCCESS
         }
-        _ => {
-            error!("failed to unset core.hooksPath");
+        Err(e) => {
+            error!("{e}");
             ExitCode::FAILURE
         }
     }
 }
 
-fn adapter_config_for(root: &Path, hook_name: Option<&str>) -> Option<Value> {
-    let adapter = adapters::detect_adapter(root)?;
-    debug!("detected adapter: {}", adapter.name());
-
-    if let Some(name) = hook_name {
-        let config = adapter.generate_config(root, name);
-        if config.is_none() {
-            debug!("adapter {} has no config for {name}", adapter.name());
+/// `reinstall`: remove lhm's hook symlinks from the hooks dir it currently
+/// uses, then `install` again, keeping that dir unless `custom_dir` is given
+/// and the binary the symlinks pointed at unless `custom_binary` is.
+fn reinstall(custom_dir: Option<&Path>, local: bool, custom_binary: Option<&Path>, profile: ConfigProfile) -> ExitCode {
+    let running = env::current_exe().expect("cannot determine lhm binary path");
+    let scope = match install_scope(local) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
         }
-        return config.map(annotate_hooks);
+    };
+    let current = doctor::git_config(scope, "core.hooksPath");
+    let previous = previous_hooks_dir(current.as_deref(), &hooks_dir(), &running);
+    let binary = custom_binary
+        .map(Path::to_path_buf)
+        .or_else(|| previous.as_deref().and_then(|dir| previous_binary(dir, &running)));
+    let Some(previous) = previous else {
+        return install(None, custom_dir, false, local, binary.as_deref(), profile);
+    };
+    let target = custom_dir.unwrap_or(&previous);
+    if let Err(e) = validate_hooks_dir(target) {
+        error!("{e}");
+        return ExitCode::FAILURE;
     }
 
-    let mut combined: Option<Value> = None;
-    for name in GIT_HOOKS {
-        if let Some(config) = adapter.generate_config(root, name) {
-            combined = Some(match combined {
-                Some(existing) => merge_configs(existing, config),
-                None => config,
-            });
-        }
+    // Moving to a new dir: install first, while the old dir's symlinks still
+    // mark core.hooksPath as lhm's, then clean the old dir up.
+    let moving = target != previous;
+    if !moving && let Err(e) = remove_hook_symlinks(&previous, &running) {
+        error!("{e}");
+        return ExitCode::FAILURE;
+    }
+    // The default dir is lhm's even without symlinks left in it
+    let force = moving && previous == hooks_dir();
+    let code = install(None, Some(target), force, local, binary.as_deref(), profile);
+    if moving
+        && code == ExitCode::SUCCESS
+        && let Err(e) = remove_hook_symlinks(&previous, &running)
+    {
+        error!("{e}");
+        return ExitCode::FAILURE;
     }
-    combined.map(annotate_hooks)
+    code
 }
 
-/// Resolve global, repo, and adapter sources into a single merged config.
-fn resolve_config(
-    global: &Option<Value>,
-    repo: &Option<PathBuf>,
-    adapter_config: &Option<Value>,
-) -> Result<Option<Value>, String> {
-    match (global, repo, adapter_config) {
-        (Some(g), Some(r), _) => {
-            let rv = read_yaml(r)?;
-            Ok(Some(merge_configs(g.clone(), rv)))
+/// The hooks dir a previous `install` set up: `current` (the `core.hooksPath`
+/// value) if it is the default dir or holds hook symlinks to an lhm binary.
+/// Symlinks to an older or moved lhm count, since replacing those is what
+/// `reinstall` is for; a dir of the user's own hooks is never adopted.
+fn previous_hooks_dir(current: Option<&str>, default: &Path, binary: &Path) -> Option<PathBuf> {
+    let current = Path::new(current?);
+    (current == default || !lhm_hook_symlinks(current, binary).is_empty()).then(|| current.to_path_buf())
+}
+
+/// The binary `dir`'s lhm hook symlinks point at (e.g. from `install
+/// --binary`), if it is an absolute path that still exists.
+fn previous_binary(dir: &Path, binary: &Path) -> Option<PathBuf> {
+    let link = lhm_hook_symlinks(dir, binary).into_iter().next()?;
+    std::fs::read_link(link)
+        .ok()
+        .filter(|target| target.is_absolute() && target.is_file())
+}
+
+/// The binary hook symlinks should point at: `custom` (`--binary`) if given,
+/// otherwise the running lhm, with a warning when that looks like a
+/// short-lived build or extraction whose symlinks would soon dangle.
+fn install_binary(custom: Option<&Path>) -> Result<PathBuf, String> {
+    if let Some(path) = custom {
+        if !path.is_absolute() {
+            return Err(format!("--binary must be an absolute path: {}", path.display()));
+        }
+        if !path.is_file() {
+            return Err(format!("--binary {} is not a file", path.display()));
         }
-        (Some(g), None, Some(av)) => Ok(Some(merge_configs(g.clone(), av.clone()))),
-        (Some(g), None, None) => Ok(Some(g.clone())),
-        (None, Some(r), _) => read_yaml(r).map(Some),
-        (None, None, Some(av)) => Ok(Some(av.clone())),
-        (None, None, None) => Ok(None),
+        return Ok(path.to_path_buf());
     }
+    let binary = env::current_exe().map_err(|e| format!("cannot determine lhm binary path: {e}"))?;
+    if let Some(location) = unstable_binary_location(&binary, &env::temp_dir()) {
+        warn!(
+            "lhm is running from {location} ({}); hook symlinks will break once it is removed. \
+             Install lhm to a durable path, or pass --binary <path>",
+            binary.display()
+        );
+    }
+    Ok(binary)
 }
 
-fn dry_run(overrides: &ConfigOverrides) -> ExitCode {
-    let global = match load_global_config(&home_dir(), overrides) {
-        Ok(v) => v,
+/// Describe why `binary` is likely ephemeral: it lives under `temp_dir` or in a
+/// cargo `target/{debug,release}` dir (e.g. from `cargo run`).
+fn unstable_binary_location(binary: &Path, temp_dir: &Path) -> Option<&'static str> {
+    let in_temp = binary.starts_with(temp_dir) || std::fs::canonicalize(temp_dir).is_ok_and(|t| binary.starts_with(t));
+    if in_temp {
+        return Some("a temporary directory");
+    }
+    let components: Vec<_> = binary.components().map(|c| c.as_os_str()).collect();
+    let in_target = components
+        .iter()
+        .enumerate()
+        .any(|(i, c)| *c == "target" && components[i + 1..].iter().any(|c| *c == "debug" || *c == "release"));
+    in_target.then_some("a cargo target directory")
+}
+
+/// The `git config` scope flag `install` writes `core.hooksPath` to.
+fn install_scope(local: bool) -> Result<&'static str, String> {
+    if !local {
+        return Ok("--global");
+    }
+    let cwd = env::current_dir().map_err(|e| format!("cannot determine current directory: {e}"))?;
+    let root = local_install_root(&cwd)?;
+    debug!("installing for repository {}", root.display());
+    Ok("--local")
+}
+
+/// `install --check`: print how the machine differs from what `install` would
+/// set up, without changing anything. Fails if there are differences.
+fn install_check(custom_dir: Option<&Path>, local: bool, custom_binary: Option<&Path>) -> ExitCode {
+    let binary = match install_binary(custom_binary) {
+        Ok(b) => b,
         Err(e) => {
             error!("{e}");
             return ExitCode::FAILURE;
         }
     };
-    let root = repo_root();
-    let repo = root.as_deref().and_then(|r| repo_config(r, overrides));
+    let scope = match install_scope(local) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let target = custom_dir.map_or_else(hooks_dir, Path::to_path_buf);
+    let current = doctor::git_config(scope, "core.hooksPath");
+    let drift = install_drift(current.as_deref(), &target, &binary);
+    if drift.is_empty() {
+        info!("install is up to date: core.hooksPath = {}", target.display());
+        return ExitCode::SUCCESS;
+    }
+    for line in &drift {
+        println!("{line}");
+    }
+    ExitCode::FAILURE
+}
 
-    let adapter_config = if repo.is_none() {
-        root.as_deref().and_then(|r| adapter_config_for(r, None))
-    } else {
-        None
+/// Differences between the current setup and what `install` would produce:
+/// `core.hooksPath` (`current`) should be `target`, holding hook symlinks to `binary`.
+fn install_drift(current: Option<&str>, target: &Path, binary: &Path) -> Vec<String> {
+    let mut drift = Vec::new();
+    match current {
+        Some(c) if Path::new(c) == target => {}
+        Some(c) => drift.push(format!("core.hooksPath is {c}, expected {}", target.display())),
+        None => drift.push(format!("core.hooksPath is not set, expected {}", target.display())),
+    }
+    drift.extend(hook_symlink_drift(target, binary));
+    drift
+}
+
+/// Refuse to replace a `core.hooksPath` owned by another tool unless `force` is set.
+/// Paths that are the install target or already hold lhm symlinks are ours.
+fn check_existing_hooks_path(current: Option<&str>, target: &Path, binary: &Path, force: bool) -> Result<(), String> {
+    let Some(current) = current else {
+        return Ok(());
     };
+    let current_path = Path::new(current);
+    if current_path == target || !lhm_hook_symlinks(current_path, binary).is_empty() {
+        return Ok(());
+    }
+    if force {
+        warn!("overwriting core.hooksPath = {current}");
+        return Ok(());
+    }
+    Err(format!(
+        "core.hooksPath is already set to {current}, which is not managed by lhm (use --force to overwrite)"
+    ))
+}
 
-    if let Some(ref p) = repo {
-        debug!("repo config: {}", p.display());
+/// The hooks dir `install` should use: `custom` (validated) or `~/.lhm/hooks`.
+fn install_dir(custom: Option<&Path>) -> Result<PathBuf, String> {
+    match custom {
+        Some(dir) => {
+            validate_hooks_dir(dir)?;
+            Ok(dir.to_path_buf())
+        }
+        None => Ok(hooks_dir()),
     }
+}
 
-    match resolve_config(&global, &repo, &adapter_config) {
-        Ok(Some(config)) => {
-            print!("{}", serde_yaml::to_string(&config).unwrap_or_default());
+/// `git config <scope> core.hooksPath <dir>`, where `scope` is `--global` or
+/// `--local`. `dir` is always absolute, so a local setting works from every
+/// worktree of the repository.
+fn set_hooks_path_command(dir: &Path, scope: &str) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args(["config", scope, "core.hooksPath"]).arg(dir);
+    cmd
+}
+
+/// The work tree root `install --local` configures when run from `cwd`.
+/// Bare repositories have no work tree to run hooks in, so they are refused.
+fn local_install_root(cwd: &Path) -> Result<PathBuf, String> {
+    let bare = Command::new("git")
+        .args(["rev-parse", "--is-bare-repository"])
+        .current_dir(cwd)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+    match bare.as_deref() {
+        None => Err("install --local must be run inside a git repository".to_string()),
+        Some("true") => Err("install --local can't be used in a bare repository (it has no work tree)".to_string()),
+        Some(_) => repo_root_in(cwd)
+            .ok_or_else(|| "install --local must be run inside a work tree, not the .git directory".to_string()),
+    }
+}
+
+fn disable() -> ExitCode {
+    let status = Command::new("git")
+        .args(["config", "--global", "--unset", "core.hooksPath"])
+        .status();
+
+    match status {
+        Ok(s) if s.success() => {
+            info!("removed core.hooksPath, lhm disabled");
             ExitCode::SUCCESS
         }
-        Ok(None) => {
-            debug!("no config to display");
+        Ok(s) if s.code() == Some(5) => {
+            // Exit code 5 means the key was not set
+            info!("core.hooksPath was not set, nothing to do");
             ExitCode::SUCCESS
         }
-        Err(e) => {
-            error!("{e}");
+        _ => {
+            error!("failed to unset core.hooksPath");
             ExitCode::FAILURE
         }
     }
 }
 
-fn lefthook_in_path() -> bool {
-    Command::new("lefthook")
-        .arg("--version")
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .is_ok()
+fn config_path(overrides: &ConfigOverrides) -> ExitCode {
+    for (label, path) in config_path_report(&home_dir(), repo_root().as_deref(), overrides) {
+        println!("{label}: {path}");
+    }
+    ExitCode::SUCCESS
 }
 
-/// Run the repo's `.git/hooks/<hook_name>` script directly.
-/// Returns SUCCESS if the script doesn't exist (no hook to run).
-fn run_git_hook(hook_name: &str, args: Vec<String>) -> ExitCode {
-    let root = match repo_root() {
-        Some(r) => r,
-        None => return ExitCode::SUCCESS,
-    };
-    let hook_path = root.join(".git/hooks").join(hook_name);
-    if !hook_path.is_file() {
-        debug!("no .git/hooks/{hook_name} found, skipping");
-        return ExitCode::SUCCESS;
+fn version() -> ExitCode {
+    for (name, version) in doctor::versions("lefthook", "git") {
+        println!("{name}: {version}");
     }
-    debug!("running .git/hooks/{hook_name} directly (lefthook not in PATH)");
-    let status = Command::new(&hook_path)
-        .args(&args)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status();
-    match status {
-        Ok(s) if s.success() => ExitCode::SUCCESS,
-        Ok(_) => ExitCode::FAILURE,
-        Err(e) => {
-            error!("failed to run .git/hooks/{hook_name}: {e}");
-            ExitCode::FAILURE
+    ExitCode::SUCCESS
+}
+
+fn doctor(overrides: &ConfigOverrides, json: bool) -> ExitCode {
+    let checks = doctor::run_checks(&home_dir(), &hooks_dir(), overrides);
+    if json {
+        println!("{}", doctor::checks_json(&checks));
+    } else {
+        let color = style::color_enabled();
+        for check in &checks {
+            println!("{} {}: {}", style::marker(check.ok, color), check.name, check.detail);
         }
     }
+    if checks.iter().all(|c| c.ok) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
-fn run_hook(hook_name: &str, args: Vec<String>, overrides: &ConfigOverrides) -> ExitCode {
-    if !lefthook_in_path() {
-        debug!("lefthook not found in PATH, falling back to .git/hooks");
-        return run_git_hook(hook_name, args);
+/// A `core.hooksPath` setting and whether it points at lhm hooks.
+struct HooksPathState {
+    scope: &'static str,
+    path: Option<PathBuf>,
+    managed: bool,
+}
+
+fn status(json: bool) -> ExitCode {
+    let binary = env::current_exe().expect("cannot determine lhm binary path");
+    let default_dir = hooks_dir();
+
+    let mut dirs = vec![default_dir.clone()];
+    let mut hooks_paths = Vec::new();
+    for scope in ["global", "local"] {
+        let path = doctor::git_config(&format!("--{scope}"), "core.hooksPath").map(PathBuf::from);
+        let managed = path
+            .as_ref()
+            .is_some_and(|p| *p == default_dir || hook_symlink_status(p, &binary).current > 0);
+        if let Some(p) = &path
+            && !dirs.contains(p)
+        {
+            dirs.push(p.clone());
+        }
+        hooks_paths.push(HooksPathState { scope, path, managed });
     }
+    let symlinks: Vec<(PathBuf, SymlinkStatus)> = dirs
+        .into_iter()
+        .map(|d| {
+            let s = hook_symlink_status(&d, &binary);
+            (d, s)
+        })
+        .collect();
 
-    let global = match load_global_config(&home_dir(), overrides) {
-        Ok(v) => v,
-        Err(e) => {
-            error!("{e}");
-            return ExitCode::FAILURE;
+    if json {
+        println!("{}", status_json(&hooks_paths, &symlinks, &binary));
+        return ExitCode::SUCCESS;
+    }
+
+    for state in &hooks_paths {
+        match &state.path {
+            Some(path) => {
+                let label = if state.managed {
+                    "lhm-managed"
+                } else {
+                    "not lhm-managed"
+                };
+                println!("{} core.hooksPath: {} ({label})", state.scope, path.display());
+            }
+            None => println!("{} core.hooksPath: not set", state.scope),
         }
-    };
-    let root = repo_root();
-    let repo = root.as_deref().and_then(|r| repo_config(r, overrides));
+    }
+    for (dir, s) in &symlinks {
+        println!(
+            "hook symlinks in {}: {}/{} present, {} point to {}",
+            dir.display(),
+            s.present,
+            GIT_HOOKS.len(),
+            s.current,
+            binary.display()
+        );
+    }
+    ExitCode::SUCCESS
+}
 
-    debug!("repo root: {:?}", root);
-    debug!("repo config: {:?}", repo);
+fn status_json(
+    hooks_paths: &[HooksPathState],
+    symlinks: &[(PathBuf, SymlinkStatus)],
+    binary: &Path,
+) -> serde_json::Value {
+    let mut paths = serde_json::Map::new();
+    for state in hooks_paths {
+        paths.insert(
+            state.scope.to_string(),
+            serde_json::json!({"path": state.path, "managed": state.managed}),
+        );
+    }
+    let symlinks: Vec<serde_json::Value> = symlinks
+        .iter()
+        .map(|(dir, s)| {
+            serde_json::json!({
+                "dir": dir,
+                "present": s.present,
+                "current": s.current,
+                "total": GIT_HOOKS.len(),
+            })
+        })
+        .collect();
+    serde_json::json!({"binary": binary, "hooksPath": paths, "symlinks": symlinks})
+}
 
-    let adapter_config = if repo.is_none() {
-        root.as_deref().and_then(|r| adapter_config_for(r, Some(hook_name)))
-    } else {
-        None
-    };
+/// Editor command from `$VISUAL`, then `$EDITOR`, then the platform default.
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|k| env::var(k).ok())
+        .find(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
 
-    let merged = match resolve_config(&global, &repo, &adapter_config) {
-        Ok(Some(m)) => m,
-        Ok(None) => {
-            debug!("no config found, skipping hook");
-            return ExitCode::SUCCESS;
-        }
+/// Ask a yes/no question on the terminal. Defaults to yes; returns `false`
+/// without asking when stdin isn't a terminal.
+fn confirm(question: &str) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("lhm: {question} [Y/n] ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes")
+}
+
+fn edit(repo: bool, overrides: &ConfigOverrides) -> ExitCode {
+    let home = home_dir();
+    let root = repo_root();
+    let path = match edit_path(&home, root.as_deref(), repo, overrides) {
+        Ok(p) => p,
         Err(e) => {
             error!("{e}");
             return ExitCode::FAILURE;
         }
     };
-    let _temp = match write_merged_temp(merged) {
-        Ok(t) => t,
-        Err(e) => {
+
+    if !repo && !path.exists() {
+        if !confirm(&format!(
+            "no global config found, create {} from the default template?",
+            path.display()
+        )) {
+            error!("no global config at {}", path.display());
+            return ExitCode::FAILURE;
+        }
+        if let Err(e) = install_default_global_config(&home, Some(&path), ConfigProfile::default()) {
             error!("{e}");
             return ExitCode::FAILURE;
         }
-    };
-    let config_path = _temp.path();
-
-    debug!("LEFTHOOK_CONFIG={}", config_path.display());
-    debug!("running: lefthook run {hook_name} --no-auto-install {}", args.join(" "));
+    }
 
-    let status = Command::new("lefthook")
-        .arg("run")
-        .arg(hook_name)
-        .arg("--no-auto-install")
-        .args(&args)
-        .env("LEFTHOOK_CONFIG", config_path)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+    let editor = editor_command();
+    debug!("editing {} with {editor}", path.display());
+    // Run through the shell so editors with arguments (e.g. `code --wait`) work
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg("lhm")
+        .arg(&path)
         .status();
-
     match status {
-        Ok(s) if s.success() => ExitCode::SUCCESS,
-        Ok(_) => ExitCode::FAILURE,
+        Ok(s) => ExitCode::from(s.code().unwrap_or(1) as u8),
         Err(e) => {
-            error!("failed to run lefthook: {e}");
+            error!("failed to run {editor}: {e}");
             ExitCode::FAILURE
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
-    use std::process::Command;
+fn validate(path: &Path) -> ExitCode {
+    let stdin = path == Path::new("-");
+    let loaded = if stdin {
+        read_config_from(std::io::stdin().lock())
+    } else {
+        read_yaml(path)
+    };
+    let config = match loaded {
+        Ok(v) => annotate_hooks(v),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let issues = validate::validate_config(&config);
+    let mut failed = false;
+    for issue in &issues {
+        match issue.severity {
+            validate::Severity::Error => {
+                failed = true;
+                error!("{}", issue.message);
+            }
+            validate::Severity::Warning => warn!("{}", issue.message),
+        }
+    }
+    if failed {
+        return ExitCode::FAILURE;
+    }
+    let name = if stdin {
+        STDIN_NAME.into()
+    } else {
+        path.display().to_string()
+    };
+    info!("{name} is valid");
+    ExitCode::SUCCESS
+}
+
+fn prune() -> ExitCode {
+    let dir = hooks_dir();
+    let binary = env::current_exe().expect("cannot determine lhm binary path");
+    match prune_hook_symlinks(&dir, &binary) {
+        Ok(pruned) if pruned.is_empty() => {
+            info!("all hook symlinks in {} are up to date", dir.display());
+            ExitCode::SUCCESS
+        }
+        Ok(pruned) => {
+            for p in &pruned {
+                match p {
+                    Pruned::Repointed(name) => info!("repointed {name} -> {}", binary.display()),
+                    Pruned::Removed(name) => info!("removed stale {name}"),
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn migrate(force: bool, overrides: &ConfigOverrides) -> ExitCode {
+    let Some(root) = repo_root() else {
+        error!("not inside a git repository");
+        return ExitCode::FAILURE;
+    };
+    let global = load_global_config(&home_dir(), overrides).ok().flatten();
+    match write_migration(&root, force, &disabled_adapters(global.as_ref())) {
+        Ok((path, count)) => {
+            info!("wrote {count} command(s) to {}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Write the adapter-generated config for `root` to `<root>/lefthook.yaml`,
+/// or with `force` over the repo config that already exists, since that one
+/// would otherwise keep taking precedence. Returns the written path and the
+/// number of generated commands.
+fn write_migration(root: &Path, force: bool, disabled: &[String]) -> Result<(PathBuf, usize), String> {
+    let existing = find_config(root, true);
+    if let Some(ref existing) = existing
+        && !force
+    {
+        return Err(format!(
+            "repo config already exists: {} (use --force to overwrite)",
+            existing.display()
+        ));
+    }
+    let path = existing.unwrap_or_else(|| root.join("lefthook.yaml"));
+    let config = adapter_config_for(root, None, true, disabled)?.ok_or("no adapter detected or nothing to migrate")?;
+    let config = strip_sources(config);
+    let count = count_commands(&config);
+    write_atomic(&path, &serialize_config(&config, &path)?)?;
+    Ok((path, count))
+}
+
+/// `config` in the format of `path`'s extension: YAML, or JSON for `.json`
+/// and `.jsonc`. TOML can't be written.
+fn serialize_config(config: &Value, path: &Path) -> Result<String, String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yml" | "yaml") => serde_yaml::to_string(config).map_err(|e| format!("failed to serialize config: {e}")),
+        Some("json" | "jsonc") => serde_json::to_string_pretty(config)
+            .map(|json| json + "\n")
+            .map_err(|e| format!("failed to serialize config: {e}")),
+        _ => Err(format!(
+            "can't write {}: only YAML and JSON configs can be migrated into (remove it and run migrate again)",
+            path.display()
+        )),
+    }
+}
+
+fn count_commands(config: &Value) -> usize {
+    config
+        .as_mapping()
+        .into_iter()
+        .flat_map(|m| m.values())
+        .filter_map(|hook| hook.get("commands").and_then(Value::as_mapping))
+        .map(|cmds| cmds.len())
+        .sum()
+}
+
+/// Generate adapter config for one hook (or every supported hook when
+/// `hook_name` is `None`), adding lhm's annotations unless `annotate` is false.
+/// Adapters named in `disabled` are never detected. An adapter pinned in
+/// `<root>/.lhm.yml` is used without detection.
+fn adapter_config_for(
+    root: &Path,
+    hook_name: Option<&str>,
+    annotate: bool,
+    disabled: &[String],
+) -> Result<Option<Value>, String> {
+    let Some(config) = raw_adapter_config(root, hook_name, disabled)? else {
+        return Ok(None);
+    };
+    let config = if annotate { annotate_hooks(config) } else { config };
+    Ok(Some(apply_ref_filters(config, &ref_filters(root)?)))
+}
+
+fn raw_adapter_config(root: &Path, hook_name: Option<&str>, disabled: &[String]) -> Result<Option<Value>, String> {
+    let adapter = match pinned_adapter(root)? {
+        Some(name) => {
+            debug!("adapter pinned by {REPO_SETTINGS_FILE}: {name}");
+            adapters::adapter_by_name(&name)?
+        }
+        None => match adapters::detect_adapter(root, disabled) {
+            Some(adapter) => {
+                debug!("detected adapter: {}", adapter.name());
+                adapter
+            }
+            None => return Ok(None),
+        },
+    };
+
+    for source in adapter.config_sources(root).iter().filter(|p| p.exists()) {
+        debug!("adapter source: {}", source.display());
+    }
+    Ok(generate_adapter_config(adapter.as_ref(), root, hook_name))
+}
+
+/// Generate `adapter`'s config for one hook, or every hook it supports when
+/// `hook_name` is `None`, marked with its `lhm_source`.
+fn generate_adapter_config(adapter: &dyn adapters::Adapter, root: &Path, hook_name: Option<&str>) -> Option<Value> {
+    let source = format!("{}-adapter", adapter.name());
+    if let Some(name) = hook_name {
+        let config = adapter.generate_config(root, name);
+        if config.is_none() {
+            debug!("adapter {} has no config for {name}", adapter.name());
+        }
+        return config.map(|c| mark_source(c, &source));
+    }
+
+    adapter.generate_all(root).map(|c| mark_source(c, &source))
+}
+
+/// Config the adapter named `name` generates for every hook in `root`,
+/// bypassing pinning and detection order. Fails if it doesn't detect `root`.
+fn selected_adapter_config(root: &Path, name: &str, annotate: bool) -> Result<Option<Value>, String> {
+    let adapter = adapters::adapter_by_name(name)?;
+    if !adapter.detect(root) {
+        return Err(format!("adapter {name} does not detect anything in {}", root.display()));
+    }
+    let config = generate_adapter_config(adapter.as_ref(), root, None);
+    Ok(config.map(|c| if annotate { annotate_hooks(c) } else { c }))
+}
+
+/// `explain`: print the adapter decision for the current repo, then whether a
+/// repo config keeps adapters from being used at all.
+fn explain(overrides: &ConfigOverrides) -> ExitCode {
+    let Some(root) = repo_root() else {
+        error!("not inside a git repository");
+        return ExitCode::FAILURE;
+    };
+    match explain_lines(&root, &home_dir(), overrides) {
+        Ok(lines) => {
+            for line in lines {
+                println!("{line}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn explain_lines(root: &Path, home: &Path, overrides: &ConfigOverrides) -> Result<Vec<String>, String> {
+    let global = load_global_config(home, overrides)?;
+    let pinned = pinned_adapter(root)?;
+    if let Some(ref name) = pinned {
+        adapters::adapter_by_name(name)?;
+    }
+    let mut lines = adapters::explain(root, &disabled_adapters(global.as_ref()), pinned.as_deref());
+    if let Some(path) = repo_config(root, overrides) {
+        let repo = load_repo_config(Some(root), Some(&path))?;
+        let effect = if lhm_flag(global.as_ref(), repo.as_ref(), "merge_adapters") {
+            "adapter merged under it (lhm.merge_adapters)"
+        } else {
+            "adapters not used"
+        };
+        lines.push(format!("repo-config: {} ({effect})", path.display()));
+    }
+    Ok(lines)
+}
+
+fn schema() -> ExitCode {
+    match serde_json::to_string_pretty(&schema::config_schema()) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("failed to serialize schema: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn dry_run_select(name: &str, annotate: bool) -> ExitCode {
+    let Some(root) = repo_root() else {
+        error!("not inside a git repository");
+        return ExitCode::FAILURE;
+    };
+    match selected_adapter_config(&root, name, annotate) {
+        Ok(Some(config)) => {
+            print!("{}", serde_yaml::to_string(&config).unwrap_or_default());
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            info!("adapter {name} generates no hooks");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// With `lhm.nested_configs: true`, merge the configs of the directories
+/// between the repo root and the current directory onto `repo`. Git runs hooks
+/// from the worktree root, so only `dry-run` from a subdirectory finds any.
+fn with_nested_configs(
+    repo: Option<Value>,
+    global: Option<&Value>,
+    root: Option<&Path>,
+) -> Result<Option<Value>, String> {
+    let Some(root) = root else {
+        return Ok(repo);
+    };
+    if !lhm_flag(global, repo.as_ref(), "nested_configs") {
+        return Ok(repo);
+    }
+    match env::current_dir().and_then(std::fs::canonicalize) {
+        Ok(cwd) => merge_nested_configs(repo, root, &cwd),
+        Err(_) => Ok(repo),
+    }
+}
+
+/// Resolve global, repo, and adapter sources into a single merged config,
+/// then apply lhm-specific rewrites (e.g. `lhm_retry`).
+///
+/// Layers merge in order global, adapter, repo, each taking precedence over
+/// the ones before it. Callers only pass adapter config alongside a repo
+/// config when `lhm.merge_adapters` asks for it.
+fn resolve_config(
+    global: &Option<Value>,
+    repo: &Option<Value>,
+    adapter_config: &Option<Value>,
+) -> Result<Option<Value>, String> {
+    resolve_hook_config(global, repo, adapter_config, None)
+}
+
+/// `resolve_config` for a run of `hook_name` only, which also applies that
+/// hook's `lhm.output` entry.
+fn resolve_hook_config(
+    global: &Option<Value>,
+    repo: &Option<Value>,
+    adapter_config: &Option<Value>,
+    hook_name: Option<&str>,
+) -> Result<Option<Value>, String> {
+    let strict = env_flag("LHM_STRICT");
+    let strategy = merge_strategy(global.as_ref(), repo.as_ref())?;
+    let mut merged: Option<Value> = None;
+    for layer in [global, adapter_config, repo].into_iter().flatten() {
+        merged = Some(match merged {
+            Some(m) => merge_configs_checked(m, layer.clone(), strict, strategy)?,
+            None => layer.clone(),
+        });
+    }
+    Ok(merged
+        .map(apply_jobs_limit)
+        .map(|c| apply_hook_output(c, hook_name))
+        .map(strip_lhm_settings)
+        .map(apply_retries))
+}
+
+/// `dry-run --stdin`: print a config read from stdin after the processing
+/// adapter output and the merged config get, without reading any config files.
+fn dry_run_stdin(annotate: bool, require_hooks: bool) -> ExitCode {
+    match process_config_from(std::io::stdin().lock(), annotate) {
+        Ok(config) => {
+            print!("{}", serde_yaml::to_string(&config).unwrap_or_default());
+            if require_hooks && !has_hooks(&config) {
+                error!("config defines no hooks");
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Read a config from `reader`, annotate it like adapter output when
+/// `annotate` is set, and resolve it as the only config layer.
+fn process_config_from(reader: impl std::io::Read, annotate: bool) -> Result<Value, String> {
+    let config = read_config_from(reader)?;
+    let config = if annotate { annotate_hooks(config) } else { config };
+    Ok(resolve_config(&Some(config), &None, &None)?.unwrap_or(Value::Null))
+}
+
+fn dry_run(overrides: &ConfigOverrides, annotate: bool, require_hooks: bool, all: bool) -> ExitCode {
+    let global = match load_global_config(&home_dir(), overrides) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let root = repo_root();
+    let repo_path = root.as_deref().and_then(|r| repo_config(r, overrides));
+
+    if let Some(ref p) = repo_path {
+        debug!("repo config: {}", p.display());
+    }
+
+    let repo = match load_repo_config(root.as_deref(), repo_path.as_deref())
+        .and_then(|r| with_nested_configs(r, global.as_ref(), root.as_deref()))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let adapters_used = repo.is_none() || lhm_flag(global.as_ref(), repo.as_ref(), "merge_adapters");
+    // `--all` shows the adapter output even when the merge doesn't use it.
+    let adapter_config = match root.as_deref() {
+        Some(r) if adapters_used || all => {
+            match adapter_config_for(r, None, annotate, &disabled_adapters(global.as_ref())) {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        _ => None,
+    };
+    let adapter_section = match root {
+        Some(_) if all => match adapter_only_section(adapter_config.as_ref(), adapters_used) {
+            Ok(section) => Some(section),
+            Err(e) => {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => None,
+    };
+    let adapter_config = adapter_config.filter(|_| adapters_used);
+    let merged = resolve_config(&global, &repo, &adapter_config);
+    print_dry_run(
+        &mut std::io::stdout().lock(),
+        merged,
+        adapter_section,
+        all,
+        require_hooks,
+    )
+}
+
+/// Print the merged config of `dry-run`, followed by the `--all` adapter
+/// section if any, which is printed even when the check fails.
+fn print_dry_run(
+    out: &mut impl std::io::Write,
+    merged: Result<Option<Value>, String>,
+    adapter_section: Option<String>,
+    all: bool,
+    require_hooks: bool,
+) -> ExitCode {
+    if all {
+        let _ = writeln!(out, "# merged config");
+    }
+    let code = match merged {
+        Ok(Some(config)) => {
+            let _ = write!(out, "{}", serde_yaml::to_string(&config).unwrap_or_default());
+            if require_hooks && !has_hooks(&config) {
+                error!("merged config defines no hooks");
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Ok(None) if require_hooks => {
+            error!("no config found");
+            ExitCode::FAILURE
+        }
+        Ok(None) => {
+            debug!("no config to display");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    };
+    if let Some(section) = adapter_section {
+        let _ = write!(out, "{section}");
+    }
+    code
+}
+
+/// `dry-run --all`: the adapter's output for every hook on its own, as a
+/// separate labeled YAML document, noting whether the merged config used it.
+fn adapter_only_section(config: Option<&Value>, used: bool) -> Result<String, String> {
+    let Some(config) = config else {
+        return Ok("---\n# adapter output: no adapter detected\n".to_string());
+    };
+    let note = if used {
+        "merged into the config above"
+    } else {
+        "not used: the repo has its own lefthook config"
+    };
+    let yaml = serde_yaml::to_string(config).map_err(|e| format!("failed to serialize adapter config: {e}"))?;
+    Ok(format!("---\n# adapter output ({note})\n{yaml}"))
+}
+
+/// Whether a config has at least one top-level git hook section.
+fn has_hooks(config: &Value) -> bool {
+    config
+        .as_mapping()
+        .is_some_and(|m| m.keys().filter_map(Value::as_str).any(is_hook_name))
+}
+
+fn lefthook_in_path() -> bool {
+    Command::new("lefthook")
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// The repo's git hooks directory. Uses `git rev-parse --git-path hooks` so
+/// linked worktrees (where `<root>/.git` is a file) resolve to the shared
+/// hooks dir of the main repository.
+fn git_hooks_dir(root: &Path) -> PathBuf {
+    Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(root)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| resolve_git_path(root, String::from_utf8_lossy(&o.stdout).trim()))
+        .unwrap_or_else(|| root.join(".git/hooks"))
+}
+
+/// `git rev-parse --git-path` prints paths relative to the directory it ran in.
+fn resolve_git_path(root: &Path, output: &str) -> PathBuf {
+    let path = Path::new(output);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        root.join(path)
+    }
+}
+
+/// Exit code for lhm's own failures during a hook run (config read or merge
+/// errors, failing to write the merged config or to start lefthook), so they
+/// can be told apart from a hook rejecting the commit.
+const EXIT_LHM_ERROR: u8 = 2;
+
+/// Exit code when `LHM_TIMEOUT_SECS` kills lefthook, as with `timeout(1)`.
+const EXIT_TIMEOUT: u8 = 124;
+
+/// Exit code to pass through from a finished hook process: its own code, or
+/// 128 + the signal number if a signal killed it.
+fn hook_exit_code(status: ExitStatus) -> u8 {
+    if let Some(code) = status.code() {
+        return code as u8;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal as u8;
+        }
+    }
+    1
+}
+
+/// Effective user id of this process.
+fn effective_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+/// Refuse to run commands that come from the repo (its config, an adapter or
+/// `.git/hooks`) as root, since a malicious checkout could use them to run
+/// anything with root privileges. Global-config-only runs are user-controlled
+/// and always allowed; `allow_root` (`LHM_ALLOW_ROOT=1`) overrides the refusal.
+fn check_root(euid: u32, allow_root: bool, repo_derived: bool) -> Result<(), String> {
+    if euid != 0 || !repo_derived {
+        return Ok(());
+    }
+    if allow_root {
+        warn!("running repository-defined hook commands as root (LHM_ALLOW_ROOT=1)");
+        return Ok(());
+    }
+    Err(
+        "refusing to run repository-defined hook commands as root: a repo's hooks can run anything with root \
+         privileges (set LHM_ALLOW_ROOT=1 to allow)"
+            .to_string(),
+    )
+}
+
+/// The `.git/hooks/<hook_name>` script the lefthook-less fallback should run,
+/// if any. Nothing runs in CI (`ci`), where the CI system populates
+/// `.git/hooks` itself, or when the script is lhm (`binary`) from an install
+/// into `.git/hooks` on old git.
+fn fallback_hook_path(root: &Path, hook_name: &str, ci: bool, binary: Option<&Path>) -> Option<PathBuf> {
+    if ci {
+        debug!("CI detected, not falling back to .git/hooks/{hook_name}");
+        return None;
+    }
+    let hook_path = git_hooks_dir(root).join(hook_name);
+    if !hook_path.is_file() {
+        debug!("no .git/hooks/{hook_name} found, skipping");
+        return None;
+    }
+    let canonical = |p: &Path| std::fs::canonicalize(p).ok();
+    if binary.is_some_and(|b| canonical(&hook_path) == canonical(b)) {
+        debug!(".git/hooks/{hook_name} is lhm, skipping");
+        return None;
+    }
+    Some(hook_path)
+}
+
+/// Run the repo's `.git/hooks/<hook_name>` script directly.
+/// Returns SUCCESS if the script doesn't exist (no hook to run).
+fn run_git_hook(hook_name: &str, args: Vec<String>) -> ExitCode {
+    let root = match repo_root() {
+        Some(r) => r,
+        None => return ExitCode::SUCCESS,
+    };
+    let binary = env::current_exe().ok();
+    let Some(hook_path) = fallback_hook_path(&root, hook_name, is_ci(|key| env::var(key).ok()), binary.as_deref())
+    else {
+        return ExitCode::SUCCESS;
+    };
+    if let Err(e) = check_root(effective_uid(), env_flag("LHM_ALLOW_ROOT"), true) {
+        error!("{e}");
+        return ExitCode::from(EXIT_LHM_ERROR);
+    }
+    debug!("running .git/hooks/{hook_name} directly (lefthook not in PATH)");
+    let status = Command::new(&hook_path)
+        .args(&args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status();
+    match status {
+        Ok(s) => ExitCode::from(hook_exit_code(s)),
+        Err(e) => {
+            error!("failed to run .git/hooks/{hook_name}: {e}");
+            ExitCode::from(EXIT_LHM_ERROR)
+        }
+    }
+}
+
+/// Render the `lefthook run` invocation for logging and hook dry runs.
+fn lefthook_command_line(hook_name: &str, extra: &[String], args: &[String], config_path: &Path) -> String {
+    format!(
+        "LEFTHOOK_CONFIG={} lefthook {}",
+        config_path.display(),
+        lefthook_args(hook_name, extra, args).join(" ")
+    )
+}
+
+/// Build the argument list for `lefthook run`.
+///
+/// `extra` lefthook flags (from `LHM_LEFTHOOK_ARGS`) follow lhm's own flags.
+/// Git's hook arguments are forwarded positionally after `--`, so lefthook
+/// exposes them as `{1}`, `{2}`, `{3}` (e.g. the message file, commit source
+/// and SHA for `prepare-commit-msg`) even when one of them starts with `-`.
+fn lefthook_args(hook_name: &str, extra: &[String], git_args: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "run".to_string(),
+        hook_name.to_string(),
+        "--no-auto-install".to_string(),
+    ];
+    args.extend(extra.iter().cloned());
+    if !git_args.is_empty() {
+        args.push("--".to_string());
+        args.extend(git_args.iter().cloned());
+    }
+    args
+}
+
+/// Extra `lefthook run` flags from `LHM_LEFTHOOK_ARGS`, split like a shell
+/// would. A `--` is dropped since it would turn the flags after it, and the
+/// git arguments, into something else; unparsable values are ignored.
+fn lefthook_extra_args(value: Option<&str>) -> Vec<String> {
+    let Some(value) = value else {
+        return Vec::new();
+    };
+    match shell_words(value) {
+        Ok(words) => words
+            .into_iter()
+            .filter(|w| {
+                let separator = w == "--";
+                if separator {
+                    warn!("ignoring `--` in LHM_LEFTHOOK_ARGS: git's hook arguments are passed after lhm's own `--`");
+                }
+                !separator
+            })
+            .collect(),
+        Err(e) => {
+            warn!("ignoring LHM_LEFTHOOK_ARGS: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// Split `s` into words like a POSIX shell, honoring single quotes, double
+/// quotes and backslash escapes. No expansion is done.
+fn shell_words(s: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing backslash".to_string()),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Hooks whose first git argument is the path of a message file.
+const MESSAGE_FILE_HOOKS: &[&str] = &["applypatch-msg", "commit-msg", "prepare-commit-msg"];
+
+/// Make the message-file argument of message hooks absolute. git passes it
+/// relative to the repo root (e.g. `.git/COMMIT_EDITMSG`), which breaks `{1}`
+/// for lefthook commands that run from a different `root`.
+fn absolutize_message_file(hook_name: &str, mut args: Vec<String>, cwd: Option<&Path>) -> Vec<String> {
+    if let (true, Some(first), Some(cwd)) = (MESSAGE_FILE_HOOKS.contains(&hook_name), args.first_mut(), cwd)
+        && Path::new(first.as_str()).is_relative()
+    {
+        *first = cwd.join(first.as_str()).display().to_string();
+    }
+    args
+}
+
+/// The merged config `run_hook` passes to lefthook for `hook_name`, before
+/// `lhm_source` markers are stripped, and whether any of it came from the repo
+/// (its config or an adapter). `None` when there is no config at all.
+fn hook_config(
+    hook_name: &str,
+    home: &Path,
+    root: Option<&Path>,
+    overrides: &ConfigOverrides,
+    tolerant: bool,
+    annotate: bool,
+) -> Result<(Option<Value>, bool), String> {
+    let (global, _) = tolerate_load_error(load_global_config(home, overrides), tolerant, "global")?;
+    let repo_path = root.and_then(|r| repo_config(r, overrides));
+
+    debug!("repo root: {:?}", root);
+    debug!("repo config: {:?}", repo_path);
+
+    let (repo, repo_skipped) = tolerate_load_error(
+        load_repo_config(root, repo_path.as_deref()).and_then(|r| with_nested_configs(r, global.as_ref(), root)),
+        tolerant,
+        "repo",
+    )?;
+
+    // An unreadable repo config falls back to the global config alone, not to an adapter
+    let adapter_config = match root {
+        Some(r) if !repo_skipped && (repo.is_none() || lhm_flag(global.as_ref(), repo.as_ref(), "merge_adapters")) => {
+            adapter_config_for(r, Some(hook_name), annotate, &disabled_adapters(global.as_ref()))?
+        }
+        _ => None,
+    };
+
+    let repo_derived = repo.is_some() || adapter_config.is_some();
+    Ok((
+        resolve_hook_config(&global, &repo, &adapter_config, Some(hook_name))?,
+        repo_derived,
+    ))
+}
+
+/// `debug-merged`: print exactly the YAML `run_hook` would write to the temp
+/// config for `hook_name`, without running lefthook.
+fn debug_merged(hook_name: &str, overrides: &ConfigOverrides) -> ExitCode {
+    if !is_hook_name(hook_name) {
+        error!("unknown git hook: {hook_name}");
+        return ExitCode::FAILURE;
+    }
+    let tolerant = env_flag("LHM_TOLERANT");
+    let annotate = !env_flag("LHM_NO_ANNOTATE");
+    let content = hook_config(
+        hook_name,
+        &home_dir(),
+        repo_root().as_deref(),
+        overrides,
+        tolerant,
+        annotate,
+    )
+    .and_then(|(merged, _)| merged.map(|m| merged_yaml(&strip_sources(m))).transpose());
+    match content {
+        Ok(Some(content)) => {
+            print!("{content}");
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            info!("no config found, {hook_name} would be skipped");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `LHM_DISABLE=1` (`disable`) turns every hook run into a successful no-op,
+/// without loading any config or running lefthook.
+fn disabled_hook_exit(hook_name: &str, disable: bool) -> Option<ExitCode> {
+    if !disable {
+        return None;
+    }
+    info!("lhm disabled by LHM_DISABLE, skipping {hook_name}");
+    Some(ExitCode::SUCCESS)
+}
+
+fn run_hook(hook_name: &str, args: Vec<String>, overrides: &ConfigOverrides) -> ExitCode {
+    if let Some(code) = disabled_hook_exit(hook_name, env_flag("LHM_DISABLE")) {
+        return code;
+    }
+    let args = absolutize_message_file(hook_name, args, env::current_dir().ok().as_deref());
+    // LHM_HOOK_DRY_RUN=1 prints what would run instead of running it
+    let hook_dry_run = env_flag("LHM_HOOK_DRY_RUN");
+    let extra = lefthook_extra_args(env::var("LHM_LEFTHOOK_ARGS").ok().as_deref());
+
+    if !lefthook_in_path() {
+        if hook_dry_run {
+            info!("dry run: lefthook not in PATH, would run .git/hooks/{hook_name}");
+            return ExitCode::SUCCESS;
+        }
+        debug!("lefthook not found in PATH, falling back to .git/hooks");
+        return run_git_hook(hook_name, args);
+    }
+
+    // LHM_TOLERANT=1 runs with the layers that did load instead of failing the hook
+    let tolerant = env_flag("LHM_TOLERANT");
+    let annotate = !env_flag("LHM_NO_ANNOTATE");
+    let (merged, repo_derived) = match hook_config(
+        hook_name,
+        &home_dir(),
+        repo_root().as_deref(),
+        overrides,
+        tolerant,
+        annotate,
+    ) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::from(EXIT_LHM_ERROR);
+        }
+    };
+
+    if !hook_dry_run && let Err(e) = check_root(effective_uid(), env_flag("LHM_ALLOW_ROOT"), repo_derived) {
+        error!("{e}");
+        return ExitCode::from(EXIT_LHM_ERROR);
+    }
+
+    let Some(merged) = merged else {
+        debug!("no config found, skipping hook");
+        return ExitCode::SUCCESS;
+    };
+    if hook_dry_run {
+        print!("{}", serde_yaml::to_string(&merged).unwrap_or_default());
+    }
+    let _temp = match write_merged_temp(strip_sources(merged)) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::from(EXIT_LHM_ERROR);
+        }
+    };
+
+    if hook_dry_run {
+        // Keep the temp config around so the printed path can be inspected
+        let config_path = match _temp.keep() {
+            Ok((_, path)) => path,
+            Err(e) => {
+                error!("failed to keep temp config: {e}");
+                return ExitCode::from(EXIT_LHM_ERROR);
+            }
+        };
+        info!(
+            "dry run: would run: {}",
+            lefthook_command_line(hook_name, &extra, &args, &config_path)
+        );
+        return ExitCode::SUCCESS;
+    }
+    let config_path = _temp.path();
+
+    debug!(
+        "running: {}",
+        lefthook_command_line(hook_name, &extra, &args, config_path)
+    );
+
+    let timeout = process::timeout_from_env();
+    let mut cmd = Command::new("lefthook");
+    cmd.args(lefthook_args(hook_name, &extra, &args))
+        .env("LEFTHOOK_CONFIG", config_path)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+
+    match process::run_with_timeout(&mut cmd, timeout) {
+        Ok(Some(s)) => ExitCode::from(hook_exit_code(s)),
+        Ok(None) => {
+            error!("lefthook timed out after {} s", timeout.map_or(0, |t| t.as_secs()));
+            ExitCode::from(EXIT_TIMEOUT)
+        }
+        Err(e) => {
+            error!("failed to run lefthook: {e}");
+            ExitCode::from(EXIT_LHM_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_install_dir_custom() {
+        let dir = tempfile::tempdir().unwrap();
+        let custom = dir.path().join("my-hooks");
+        let resolved = install_dir(Some(&custom)).unwrap();
+        assert_eq!(resolved, custom);
+
+        let fake_binary = dir.path().join("lhm");
+        fs::write(&fake_binary, "fake").unwrap();
+        create_hook_symlinks(&resolved, &fake_binary).unwrap();
+        assert!(
+            custom
+                .join("pre-commit")
+                .symlink_metadata()
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+
+        let cmd = set_hooks_path_command(&resolved, "--global");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args.last().copied(), Some(custom.as_os_str()));
+    }
+
+    #[test]
+    fn test_set_hooks_path_command_local_is_absolute() {
+        let cmd = set_hooks_path_command(&install_dir(None).unwrap(), "--local");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args[..2], ["config", "--local"]);
+        assert!(Path::new(args[3]).is_absolute(), "{:?}", args[3]);
+    }
+
+    #[test]
+    fn test_install_drift_up_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "fake").unwrap();
+        create_hook_symlinks(&hooks, &binary).unwrap();
+
+        assert!(install_drift(hooks.to_str(), &hooks, &binary).is_empty());
+    }
+
+    #[test]
+    fn test_install_drift_reports_differences() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "fake").unwrap();
+        create_hook_symlinks(&hooks, &binary).unwrap();
+        fs::remove_file(hooks.join("pre-commit")).unwrap();
+
+        let drift = install_drift(Some("/opt/other-hooks"), &hooks, &binary);
+        assert_eq!(drift.len(), 2, "{drift:?}");
+        assert_eq!(
+            drift[0],
+            format!("core.hooksPath is /opt/other-hooks, expected {}", hooks.display())
+        );
+        assert!(drift[1].contains("pre-commit is not a symlink"), "{drift:?}");
+
+        let drift = install_drift(None, &hooks, &binary);
+        assert!(drift[0].starts_with("core.hooksPath is not set"), "{drift:?}");
+        // Checking changes nothing
+        assert!(!hooks.join("pre-commit").exists());
+    }
+
+    #[test]
+    fn test_hook_config_matches_temp_file() {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(
+            home.path().join(".lefthook.yaml"),
+            "pre-commit:\n  commands:\n    fmt:\n      run: cargo fmt --check\n",
+        )
+        .unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join(".hooks")).unwrap();
+        fs::write(repo.path().join(".hooks/pre-commit"), "#!/bin/sh\nmake lint\n").unwrap();
+        let overrides = ConfigOverrides::default();
+
+        let (merged, repo_derived) =
+            hook_config("pre-commit", home.path(), Some(repo.path()), &overrides, false, true).unwrap();
+        assert!(repo_derived);
+        let content = merged_yaml(&strip_sources(merged.clone().unwrap())).unwrap();
+        assert!(content.contains("hooks-pre-commit"), "{content}");
+        assert!(content.contains("stage_fixed: true"), "{content}");
+        assert!(!content.contains("lhm_source"), "{content}");
+
+        let temp = write_merged_temp(strip_sources(merged.unwrap())).unwrap();
+        assert_eq!(fs::read_to_string(temp.path()).unwrap(), content);
+
+        let (merged, repo_derived) = hook_config("pre-commit", home.path(), None, &overrides, false, true).unwrap();
+        assert!(!repo_derived);
+        assert!(!merged_yaml(&merged.unwrap()).unwrap().contains("hooks-pre-commit"));
+    }
+
+    #[test]
+    fn test_fallback_hook_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join(".git/hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "fake").unwrap();
+        std::os::unix::fs::symlink(&binary, hooks.join("pre-push")).unwrap();
+
+        assert_eq!(
+            fallback_hook_path(dir.path(), "pre-commit", false, Some(&binary)),
+            Some(hooks.join("pre-commit"))
+        );
+        assert_eq!(fallback_hook_path(dir.path(), "pre-commit", true, Some(&binary)), None);
+        assert_eq!(fallback_hook_path(dir.path(), "pre-push", false, Some(&binary)), None);
+        assert_eq!(fallback_hook_path(dir.path(), "commit-msg", false, Some(&binary)), None);
+    }
+
+    #[test]
+    fn test_print_dry_run_all_keeps_adapter_section_when_require_hooks_fails() {
+        let merged: Value = serde_yaml::from_str("skip_lfs: true\n").unwrap();
+        let section = adapter_only_section(None, true).unwrap();
+        let mut out = Vec::new();
+        let code = print_dry_run(&mut out, Ok(Some(merged)), Some(section), true, true);
+        assert_eq!(code, ExitCode::FAILURE);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# merged config\nskip_lfs: true\n---\n# adapter output: no adapter detected\n"
+        );
+    }
+
+    #[test]
+    fn test_adapter_only_section_with_repo_config() {
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join(".hooks")).unwrap();
+        fs::write(repo.path().join(".hooks/pre-commit"), "#!/bin/sh\nmake lint\n").unwrap();
+        fs::write(repo.path().join("lefthook.yml"), "pre-commit:\n  commands: {}\n").unwrap();
+
+        let config = adapter_config_for(repo.path(), None, true, &[]).unwrap();
+        let section = adapter_only_section(config.as_ref(), false).unwrap();
+        assert!(
+            section.starts_with("---\n# adapter output (not used: the repo has its own lefthook config)\n"),
+            "{section}"
+        );
+        let config: Value = serde_yaml::from_str(&section).unwrap();
+        assert_eq!(
+            config["pre-commit"]["commands"]["hooks-pre-commit"]["lhm_source"].as_str(),
+            Some("hooks-dir-adapter")
+        );
+
+        let section = adapter_only_section(None, true).unwrap();
+        assert_eq!(section, "---\n# adapter output: no adapter detected\n");
+    }
+
+    #[test]
+    fn test_explain_lines() {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(
+            home.path().join(".lefthook.yaml"),
+            "lhm:\n  disable_adapters: [husky]\n",
+        )
+        .unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join(".husky")).unwrap();
+        fs::create_dir_all(repo.path().join(".hooks")).unwrap();
+        let overrides = ConfigOverrides::default();
+
+        let lines = explain_lines(repo.path(), home.path(), &overrides).unwrap();
+        assert_eq!(
+            lines[1],
+            "husky: detected (found .husky), disabled by lhm.disable_adapters"
+        );
+        assert_eq!(lines[5], "selected: hooks-dir (first detected in priority order)");
+        assert_eq!(lines.len(), 6);
+
+        fs::write(repo.path().join("lefthook.yml"), "pre-commit: {}\n").unwrap();
+        let lines = explain_lines(repo.path(), home.path(), &overrides).unwrap();
+        assert_eq!(
+            lines[6],
+            format!(
+                "repo-config: {} (adapters not used)",
+                repo.path().join("lefthook.yml").display()
+            )
+        );
+
+        fs::write(repo.path().join(".lhm.yml"), "adapter: overcommit\n").unwrap();
+        let err = explain_lines(repo.path(), home.path(), &overrides).unwrap_err();
+        assert!(err.starts_with("unknown adapter 'overcommit'"), "{err}");
+    }
+
+    #[test]
+    fn test_disabled_hook_exit() {
+        assert_eq!(disabled_hook_exit("pre-commit", true), Some(ExitCode::SUCCESS));
+        assert_eq!(disabled_hook_exit("pre-commit", false), None);
+    }
+
+    #[test]
+    fn test_install_mode() {
+        assert_eq!(install_mode(Some((2, 43)), false), Ok(InstallMode::HooksPath));
+        assert_eq!(install_mode(Some((2, 9)), true), Ok(InstallMode::HooksPath));
+        assert_eq!(install_mode(None, false), Ok(InstallMode::HooksPath));
+        assert_eq!(install_mode(Some((2, 8)), true), Ok(InstallMode::GitHooksDir));
+        assert_eq!(install_mode(Some((1, 9)), true), Ok(InstallMode::GitHooksDir));
+        let err = install_mode(Some((2, 8)), false).unwrap_err();
+        assert!(err.starts_with("git 2.8 doesn't support core.hooksPath"), "{err}");
+    }
+
+    #[test]
+    fn test_previous_hooks_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let default = dir.path().join("default");
+        let old_binary = dir.path().join("old").join("lhm");
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "new").unwrap();
+        create_hook_symlinks(&hooks, &old_binary).unwrap();
+
+        assert_eq!(previous_hooks_dir(hooks.to_str(), &default, &binary), Some(hooks));
+        assert_eq!(
+            previous_hooks_dir(default.to_str(), &default, &binary),
+            Some(default.clone())
+        );
+        let other = dir.path().join("husky");
+        fs::create_dir_all(&other).unwrap();
+        fs::write(other.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        assert_eq!(previous_hooks_dir(other.to_str(), &default, &binary), None);
+        assert_eq!(previous_hooks_dir(None, &default, &binary), None);
+    }
+
+    #[test]
+    fn test_previous_hooks_dir_ignores_user_owned_dir() {
+        // core.hooksPath=.githooks with tracked symlinks to the repo's scripts
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "lhm").unwrap();
+        let githooks = dir.path().join(".githooks");
+        fs::create_dir_all(&githooks).unwrap();
+        fs::write(dir.path().join("check.sh"), "#!/bin/sh\n").unwrap();
+        std::os::unix::fs::symlink("../check.sh", githooks.join("pre-commit")).unwrap();
+
+        let default = dir.path().join("default");
+        assert_eq!(previous_hooks_dir(githooks.to_str(), &default, &binary), None);
+        assert!(check_existing_hooks_path(githooks.to_str(), &default, &binary, false).is_err());
+        assert_eq!(remove_hook_symlinks(&githooks, &binary).unwrap(), 0);
+        assert!(githooks.join("pre-commit").symlink_metadata().is_ok());
+    }
+
+    #[test]
+    fn test_previous_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let running = dir.path().join("lhm");
+        fs::create_dir_all(dir.path().join("opt")).unwrap();
+        let custom = dir.path().join("opt").join("lhm");
+        fs::write(&custom, "lhm").unwrap();
+        let hooks = dir.path().join("hooks");
+        create_hook_symlinks(&hooks, &custom).unwrap();
+
+        assert_eq!(previous_binary(&hooks, &running), Some(custom.clone()));
+        // a binary that was moved away can't be kept
+        fs::remove_file(&custom).unwrap();
+        assert_eq!(previous_binary(&hooks, &running), None);
+    }
+
+    #[test]
+    fn test_install_binary_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "").unwrap();
+        assert_eq!(install_binary(Some(&binary)), Ok(binary));
+        let err = install_binary(Some(Path::new("bin/lhm"))).unwrap_err();
+        assert!(err.contains("absolute"), "{err}");
+        let err = install_binary(Some(&dir.path().join("missing"))).unwrap_err();
+        assert!(err.contains("not a file"), "{err}");
+    }
+
+    #[test]
+    fn test_unstable_binary_location() {
+        let temp = Path::new("/var/tmp-lhm-test");
+        let unstable = |p: &str| unstable_binary_location(Path::new(p), temp);
+        assert_eq!(unstable("/var/tmp-lhm-test/x/lhm"), Some("a temporary directory"));
+        assert_eq!(
+            unstable("/home/me/lhm/target/debug/lhm"),
+            Some("a cargo target directory")
+        );
+        assert_eq!(
+            unstable("/home/me/lhm/target/x86_64-unknown-linux-gnu/release/lhm"),
+            Some("a cargo target directory")
+        );
+        assert_eq!(unstable("/home/me/.cargo/bin/lhm"), None);
+        assert_eq!(unstable("/opt/homebrew/bin/lhm"), None);
+        assert_eq!(unstable("/home/me/target/lhm"), None);
+    }
+
+    #[test]
+    fn test_repo_root_through_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        assert!(
+            Command::new("git")
+                .args(["init", "-q"])
+                .arg(&repo)
+                .status()
+                .unwrap()
+                .success()
+        );
+        fs::create_dir_all(repo.join("src")).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&repo, &link).unwrap();
+
+        let canonical = fs::canonicalize(&repo).unwrap();
+        assert_eq!(repo_root_in(&link).unwrap(), canonical);
+        assert_eq!(repo_root_in(&link.join("src")).unwrap(), canonical);
+        assert_eq!(repo_root_in(&repo).unwrap(), canonical);
+    }
+
+    #[test]
+    fn test_local_install_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let bare = dir.path().join("bare.git");
+        let git = |args: &[&str]| assert!(Command::new("git").args(args).status().unwrap().success());
+        git(&["init", "-q", repo.to_str().unwrap()]);
+        git(&["init", "-q", "--bare", bare.to_str().unwrap()]);
+        fs::create_dir_all(repo.join("sub")).unwrap();
+
+        let root = local_install_root(&repo.join("sub")).unwrap();
+        assert_eq!(fs::canonicalize(root).unwrap(), fs::canonicalize(&repo).unwrap());
+
+        let err = local_install_root(&bare).unwrap_err();
+        assert!(err.contains("bare repository"), "{err}");
+        assert!(local_install_root(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_adapter_config_for_no_annotate() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-push"), "#!/bin/sh\n").unwrap();
+
+        let annotated = adapter_config_for(dir.path(), Some("pre-push"), true, &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(annotated["pre-push"]["parallel"], Value::Bool(true));
+
+        let raw = adapter_config_for(dir.path(), Some("pre-push"), false, &[])
+            .unwrap()
+            .unwrap();
+        assert!(raw["pre-push"].get("parallel").is_none());
+        let raw_all = adapter_config_for(dir.path(), None, false, &[]).unwrap().unwrap();
+        assert!(raw_all["pre-push"].get("parallel").is_none());
+    }
+
+    #[test]
+    fn test_write_migration_from_hooks_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join(".hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks.join("pre-push"), "#!/bin/sh\n").unwrap();
+        fs::write(hooks.join("pre-push-lint"), "#!/bin/sh\n").unwrap();
+
+        let (path, count) = write_migration(dir.path(), false, &[]).unwrap();
+        assert_eq!(path, dir.path().join("lefthook.yaml"));
+        assert_eq!(count, 3);
+
+        let written = read_yaml(&path).unwrap();
+        assert!(written["pre-commit"]["commands"].get("hooks-pre-commit").is_some());
+        assert!(written["pre-push"]["commands"].get("hooks-pre-push-lint").is_some());
+        assert_eq!(written["pre-push"]["parallel"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_write_migration_refuses_existing_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("lefthook.yaml"), "# hand written\n").unwrap();
+
+        let err = write_migration(dir.path(), false, &[]).unwrap_err();
+        assert!(err.contains("--force"), "{err}");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lefthook.yaml")).unwrap(),
+            "# hand written\n"
+        );
+
+        write_migration(dir.path(), true, &[]).unwrap();
+        let written = read_yaml(&dir.path().join("lefthook.yaml")).unwrap();
+        assert!(written.get("pre-commit").is_some());
+    }
+
+    #[test]
+    fn test_write_migration_force_overwrites_existing_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-commit"), "#!/bin/sh\n").unwrap();
+        fs::write(dir.path().join("lefthook.yml"), "# hand written\n").unwrap();
+
+        let (path, _) = write_migration(dir.path(), true, &[]).unwrap();
+        assert_eq!(path, dir.path().join("lefthook.yml"));
+        assert!(!dir.path().join("lefthook.yaml").exists());
+        assert_eq!(find_config(dir.path(), true), Some(path.clone()));
+        assert!(read_yaml(&path).unwrap().get("pre-commit").is_some());
+
+        fs::remove_file(&path).unwrap();
+        fs::create_dir_all(dir.path().join(".config")).unwrap();
+        let json = dir.path().join(".config").join("lefthook.json");
+        fs::write(&json, "{}").unwrap();
+        write_migration(dir.path(), true, &[]).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+        assert!(written.get("pre-commit").is_some());
+
+        fs::remove_file(&json).unwrap();
+        fs::write(dir.path().join("lefthook.toml"), "").unwrap();
+        let err = write_migration(dir.path(), true, &[]).unwrap_err();
+        assert!(err.contains("lefthook.toml"), "{err}");
+        assert_eq!(fs::read_to_string(dir.path().join("lefthook.toml")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_status_json() {
+        let hooks_paths = [
+            HooksPathState {
+                scope: "global",
+                path: Some(PathBuf::from("/home/u/.lhm/hooks")),
+                managed: true,
+            },
+            HooksPathState {
+                scope: "local",
+                path: None,
+                managed: false,
+            },
+        ];
+        let symlinks = [(
+            PathBuf::from("/home/u/.lhm/hooks"),
+            SymlinkStatus {
+                present: 13,
+                current: 12,
+            },
+        )];
+        let out = status_json(&hooks_paths, &symlinks, Path::new("/bin/lhm"));
+        let parsed: serde_json::Value = serde_json::from_str(&out.to_string()).unwrap();
+        assert_eq!(parsed["hooksPath"]["global"]["path"], "/home/u/.lhm/hooks");
+        assert_eq!(parsed["hooksPath"]["global"]["managed"], true);
+        assert!(parsed["hooksPath"]["local"]["path"].is_null());
+        assert_eq!(parsed["symlinks"][0]["current"], 12);
+        assert_eq!(parsed["symlinks"][0]["total"], GIT_HOOKS.len());
+        assert_eq!(parsed["binary"], "/bin/lhm");
+    }
+
+    #[test]
+    fn test_has_hooks() {
+        let with_hooks: Value = serde_yaml::from_str("skip_lfs: true\npre-push:\n  commands: {}\n").unwrap();
+        assert!(has_hooks(&with_hooks));
+
+        let settings_only: Value = serde_yaml::from_str("skip_lfs: true\noutput: [failure]\n").unwrap();
+        assert!(!has_hooks(&settings_only));
+        assert!(!has_hooks(&Value::Mapping(Default::default())));
+        assert!(!has_hooks(&Value::Null));
+    }
+
+    #[test]
+    fn test_adapter_config_for_disabled_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-push"), "#!/bin/sh\n").unwrap();
+
+        let disabled = ["hooks-dir".to_string()];
+        assert!(
+            adapter_config_for(dir.path(), Some("pre-push"), true, &disabled)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_adapter_config_for_pinned_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".husky")).unwrap();
+        fs::write(dir.path().join(".husky").join("pre-push"), "#!/bin/sh\n").unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-push"), "#!/bin/sh\n").unwrap();
+
+        let detected = adapter_config_for(dir.path(), Some("pre-push"), false, &[])
+            .unwrap()
+            .unwrap();
+        assert!(detected["pre-push"]["commands"].get("husky").is_some());
+
+        fs::write(dir.path().join(".lhm.yml"), "adapter: hooks-dir\n").unwrap();
+        let pinned = adapter_config_for(dir.path(), Some("pre-push"), false, &[])
+            .unwrap()
+            .unwrap();
+        assert!(pinned["pre-push"]["commands"].get("hooks-pre-push").is_some());
+        assert!(pinned["pre-push"]["commands"].get("husky").is_none());
+    }
+
+    #[test]
+    fn test_adapter_config_for_unknown_pinned_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".lhm.yml"), "adapter: overcommit\n").unwrap();
+
+        let err = adapter_config_for(dir.path(), None, true, &[]).unwrap_err();
+        assert!(err.starts_with("unknown adapter 'overcommit'"), "{err}");
+    }
+
+    #[test]
+    fn test_resolve_config_strips_lhm_settings() {
+        let global: Value = serde_yaml::from_str("lhm:\n  disable_adapters: [husky]\nskip_lfs: true\n").unwrap();
+        let merged = resolve_config(&Some(global), &None, &None).unwrap().unwrap();
+        assert!(merged.get("lhm").is_none());
+        assert_eq!(merged["skip_lfs"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_selected_adapter_config_pre_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".pre-commit-config.yaml"),
+            "repos:\n  - repo: local\n    hooks:\n      - id: fmt\n        entry: fmt\n        stages: [pre-commit]\n      - id: test\n        entry: test\n        stages: [pre-push]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-push"), "#!/bin/sh\n").unwrap();
+        // Pinning another adapter doesn't affect an explicit selection
+        fs::write(dir.path().join(".lhm.yml"), "adapter: hooks-dir\n").unwrap();
+
+        let config = selected_adapter_config(dir.path(), "pre-commit", false)
+            .unwrap()
+            .unwrap();
+        let hooks: Vec<_> = config.as_mapping().unwrap().keys().filter_map(Value::as_str).collect();
+        assert_eq!(hooks, ["pre-commit", "pre-push"]);
+        assert_eq!(
+            config["pre-push"]["commands"]["test"]["run"].as_str(),
+            Some("test {push_files}")
+        );
+        assert_eq!(
+            config["pre-push"]["commands"]["test"]["lhm_source"].as_str(),
+            Some("pre-commit-adapter")
+        );
+        assert!(config["pre-push"].get("parallel").is_none());
+
+        let annotated = selected_adapter_config(dir.path(), "pre-commit", true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(annotated["pre-push"]["parallel"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_selected_adapter_config_not_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = selected_adapter_config(dir.path(), "pre-commit", true).unwrap_err();
+        assert!(err.starts_with("adapter pre-commit does not detect anything"), "{err}");
+        assert!(selected_adapter_config(dir.path(), "nope", true).is_err());
+    }
+
+    #[test]
+    fn test_adapter_source_marker_only_in_dry_run_output() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hooks")).unwrap();
+        fs::write(dir.path().join(".hooks").join("pre-push"), "#!/bin/sh\n").unwrap();
+        let global: Value = serde_yaml::from_str("pre-push:\n  commands:\n    test:\n      run: just test\n").unwrap();
+
+        let adapter = adapter_config_for(dir.path(), Some("pre-push"), true, &[]).unwrap();
+        let merged = resolve_config(&Some(global), &None, &adapter).unwrap().unwrap();
+        // dry-run prints the merged config as is, labelling only the adapter's commands
+        assert_eq!(
+            merged["pre-push"]["commands"]["hooks-pre-push"]["lhm_source"].as_str(),
+            Some("hooks-dir-adapter")
+        );
+        assert!(merged["pre-push"]["commands"]["test"].get("lhm_source").is_none());
+
+        let temp = write_merged_temp(strip_sources(merged)).unwrap();
+        let written = fs::read_to_string(temp.path()).unwrap();
+        assert!(!written.contains("lhm_source"), "{written}");
+        assert!(written.contains("just test"), "{written}");
+    }
+
+    /// Adapter whose config sets top-level lefthook settings next to its hook.
+    struct SettingsAdapter;
+
+    impl adapters::Adapter for SettingsAdapter {
+        fn name(&self) -> &str {
+            "settings"
+        }
+
+        fn detect(&self, _root: &Path) -> bool {
+            true
+        }
+
+        fn generate_config(&self, _root: &Path, hook_name: &str) -> Option<Value> {
+            let yaml =
+                format!("skip_lfs: true\ncolors: false\n{hook_name}:\n  commands:\n    lint:\n      run: lint\n");
+            serde_yaml::from_str(&yaml).ok()
+        }
+
+        fn supported_hooks(&self, _root: &Path) -> Vec<String> {
+            vec!["pre-commit".to_string(), "pre-push".to_string()]
+        }
+
+        fn detection_markers(&self) -> &'static [&'static str] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn test_adapter_top_level_settings_survive_merge_and_annotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let global: Value = serde_yaml::from_str("colors: true\noutput: [failure]\n").unwrap();
+
+        for hook_name in [Some("pre-commit"), None] {
+            let adapter = generate_adapter_config(&SettingsAdapter, dir.path(), hook_name).map(annotate_hooks);
+            let merged = resolve_config(&Some(global.clone()), &None, &adapter).unwrap().unwrap();
+            let merged = strip_sources(merged);
+
+            assert_eq!(merged["skip_lfs"], Value::Bool(true), "{hook_name:?}");
+            // adapter settings win like repo settings
+            assert_eq!(merged["colors"], Value::Bool(false), "{hook_name:?}");
+            assert!(merged.get("output").is_some(), "{hook_name:?}");
+            assert_eq!(
+                merged["pre-commit"]["commands"]["lint"]["stage_fixed"],
+                Value::Bool(true),
+                "{hook_name:?}"
+            );
+            assert!(crate::validate::validate_config(&merged).is_empty(), "{hook_name:?}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_config_empty_repo_config_keeps_global() {
+        let global: Value = serde_yaml::from_str("pre-commit:\n  commands:\n    lint:\n      run: lint\n").unwrap();
+        for content in ["", "# nothing here yet\n# pre-commit:\n"] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("lefthook.yml");
+            fs::write(&path, content).unwrap();
+            let repo = load_repo_config(Some(dir.path()), Some(&path)).unwrap();
+            assert_eq!(repo, Some(Value::Null));
+
+            let merged = resolve_config(&Some(global.clone()), &repo, &None).unwrap().unwrap();
+            assert_eq!(merged, global, "{content:?}");
+        }
+        let merged = resolve_config(&Some(global.clone()), &None, &Some(Value::Null))
+            .unwrap()
+            .unwrap();
+        assert_eq!(merged, global);
+    }
+
+    #[test]
+    fn test_resolve_config_consumes_lhm_jobs() {
+        let global: Value = serde_yaml::from_str("lhm:\n  jobs: 1\npre-push:\n  parallel: true\n").unwrap();
+        let merged = resolve_config(&Some(global), &None, &None).unwrap().unwrap();
+        let out = serde_yaml::to_string(&merged).unwrap();
+        assert!(!out.contains("jobs") && !out.contains("lhm"), "{out}");
+        assert!(merged["pre-push"].get("parallel").is_none(), "{out}");
+    }
+
+    #[test]
+    fn test_resolve_config_keeps_global_lhm_settings_next_to_repo_ones() {
+        let global: Value = serde_yaml::from_str("lhm:\n  jobs: 1\npre-push:\n  parallel: true\n").unwrap();
+        let repo: Value = serde_yaml::from_str("lhm:\n  merge_adapters: true\n").unwrap();
+        let merged = resolve_config(&Some(global), &Some(repo), &None).unwrap().unwrap();
+        // the global `lhm.jobs: 1` still applies
+        assert!(merged["pre-push"].get("parallel").is_none(), "{merged:?}");
+        assert!(merged.get("lhm").is_none());
+    }
+
+    #[test]
+    fn test_process_config_from_reader() {
+        let input = "lhm:\n  jobs: 1\npre-commit:\n  commands:\n    fmt:\n      run: cargo fmt\n      lhm_retry: 2\npre-push:\n  commands:\n    test:\n      run: just test\n";
+        let config = process_config_from(std::io::Cursor::new(input), true).unwrap();
+        assert!(config.get("lhm").is_none());
+        assert_eq!(
+            config["pre-commit"]["commands"]["fmt"]["stage_fixed"],
+            Value::Bool(true)
+        );
+        assert!(config["pre-commit"]["commands"]["fmt"].get("lhm_retry").is_none());
+        // `lhm.jobs: 1` removes the parallel annotation again
+        assert!(config["pre-push"].get("parallel").is_none());
+
+        let config = process_config_from(std::io::Cursor::new(input), false).unwrap();
+        assert!(config["pre-commit"]["commands"]["fmt"].get("stage_fixed").is_none());
+
+        assert_eq!(
+            process_config_from(std::io::Cursor::new(""), true).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_resolve_config_applies_lhm_output() {
+        let global: Value =
+            serde_yaml::from_str("lhm:\n  output:\n    pre-push: [execution, failure]\npre-push:\n  commands: {}\n")
+                .unwrap();
+        let repo: Value = serde_yaml::from_str("pre-push:\n  commands:\n    test:\n      run: just test\n").unwrap();
+        let merged = resolve_hook_config(&Some(global.clone()), &Some(repo.clone()), &None, Some("pre-push"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            merged["output"],
+            serde_yaml::from_str::<Value>("[execution, failure]").unwrap()
+        );
+        assert!(merged["pre-push"].get("output").is_none());
+        assert!(merged.get("lhm").is_none());
+
+        // not for a single hook, as in a dry-run of every hook
+        let merged = resolve_config(&Some(global), &Some(repo), &None).unwrap().unwrap();
+        assert!(merged.get("output").is_none());
+    }
+
+    #[test]
+    fn test_resolve_config_global_adapter_and_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".pre-commit-config.yaml"),
+            "repos:\n  - repo: local\n    hooks:\n      - id: fmt\n        entry: adapter-fmt\n      - id: mypy\n        entry: mypy\n",
+        )
+        .unwrap();
+        let global: Value =
+            serde_yaml::from_str("pre-commit:\n  commands:\n    secrets:\n      run: gitleaks\n").unwrap();
+        let repo: Value = serde_yaml::from_str(
+            "lhm:\n  merge_adapters: true\npre-commit:\n  commands:\n    fmt:\n      run: repo-fmt\n",
+        )
+        .unwrap();
+        assert!(lhm_flag(Some(&global), Some(&repo), "merge_adapters"));
+
+        let adapter = adapter_config_for(dir.path(), Some("pre-commit"), false, &[]).unwrap();
+        let merged = resolve_config(&Some(global), &Some(repo), &adapter).unwrap().unwrap();
+        let commands = &merged["pre-commit"]["commands"];
+        assert_eq!(commands["secrets"]["run"].as_str(), Some("gitleaks"));
+        assert_eq!(commands["mypy"]["run"].as_str(), Some("mypy {staged_files}"));
+        assert_eq!(commands["fmt"]["run"].as_str(), Some("repo-fmt"));
+        assert!(merged.get("lhm").is_none());
+    }
+
+    #[test]
+    fn test_write_migration_without_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(write_migration(dir.path(), false, &[]).is_err());
+        assert!(!dir.path().join("lefthook.yaml").exists());
+    }
+
+    #[test]
+    fn test_check_existing_hooks_path_unset() {
+        let target = Path::new("/home/u/.lhm/hooks");
+        assert!(check_existing_hooks_path(None, target, Path::new("/bin/lhm"), false).is_ok());
+    }
+
+    #[test]
+    fn test_check_existing_hooks_path_same_target() {
+        let target = Path::new("/home/u/.lhm/hooks");
+        assert!(check_existing_hooks_path(Some("/home/u/.lhm/hooks"), target, Path::new("/bin/lhm"), false).is_ok());
+    }
+
+    #[test]
+    fn test_check_existing_hooks_path_refuses_other_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let other = dir.path().join("husky");
+        fs::create_dir_all(&other).unwrap();
+        let other = other.to_str().unwrap();
+        let target = Path::new("/home/u/.lhm/hooks");
+
+        let err = check_existing_hooks_path(Some(other), target, Path::new("/bin/lhm"), false).unwrap_err();
+        assert!(err.contains(other), "{err}");
+        assert!(err.contains("--force"), "{err}");
+
+        assert!(check_existing_hooks_path(Some(other), target, Path::new("/bin/lhm"), true).is_ok());
+    }
+
+    #[test]
+    fn test_check_existing_hooks_path_previous_lhm_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("lhm");
+        fs::write(&binary, "fake").unwrap();
+        let old = dir.path().join("old-hooks");
+        create_hook_symlinks(&old, &binary).unwrap();
+
+        let target = dir.path().join("new-hooks");
+        assert!(check_existing_hooks_path(old.to_str(), &target, &binary, false).is_ok());
+    }
+
+    #[test]
+    fn test_install_dir_rejects_relative() {
+        assert!(install_dir(Some(Path::new("hooks"))).is_err());
+    }
+
+    #[test]
+    fn test_log_level_default_is_info() {
+        assert_eq!(log_level(0, false), log::LevelFilter::Info);
+    }
+
+    #[test]
+    fn test_log_level_quiet() {
+        assert_eq!(log_level(0, true), log::LevelFilter::Warn);
+    }
+
+    #[test]
+    fn test_log_level_verbose_counts() {
+        assert_eq!(log_level(1, false), log::LevelFilter::Debug);
+        assert_eq!(log_level(2, false), log::LevelFilter::Trace);
+        assert_eq!(log_level(5, false), log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_log_level_verbose_beats_quiet() {
+        assert_eq!(log_level(1, true), log::LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_lefthook_command_line() {
+        let args = vec![".git/COMMIT_EDITMSG".to_string(), "message".to_string()];
+        let line = lefthook_command_line("prepare-commit-msg", &[], &args, Path::new("/tmp/lhm.yml"));
+        assert_eq!(
+            line,
+            "LEFTHOOK_CONFIG=/tmp/lhm.yml lefthook run prepare-commit-msg --no-auto-install -- .git/COMMIT_EDITMSG message"
+        );
+    }
+
+    #[test]
+    fn test_lefthook_command_line_no_args() {
+        let line = lefthook_command_line("pre-commit", &[], &[], Path::new("/tmp/lhm.yml"));
+        assert_eq!(
+            line,
+            "LEFTHOOK_CONFIG=/tmp/lhm.yml lefthook run pre-commit --no-auto-install"
+        );
+    }
+
+    #[test]
+    fn test_check_root() {
+        assert!(check_root(1000, false, true).is_ok());
+        assert!(
+            check_root(0, false, false).is_ok(),
+            "global-only config is user-controlled"
+        );
+        let err = check_root(0, false, true).unwrap_err();
+        assert!(err.contains("as root") && err.contains("LHM_ALLOW_ROOT=1"), "{err}");
+        assert!(check_root(0, true, true).is_ok());
+    }
+
+    #[test]
+    fn test_hook_exit_code_passes_through() {
+        let exit = |script: &str| hook_exit_code(Command::new("sh").arg("-c").arg(script).status().unwrap());
+        assert_eq!(exit("exit 0"), 0);
+        assert_eq!(exit("exit 1"), 1);
+        assert_eq!(exit("exit 3"), 3);
+        assert_eq!(exit("kill -TERM $$"), 128 + 15);
+    }
+
+    #[test]
+    fn test_lefthook_args_prepare_commit_msg_positions() {
+        let git_args: Vec<String> = [".git/COMMIT_EDITMSG", "commit", "abc123"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let args = lefthook_args("prepare-commit-msg", &[], &git_args);
+        assert_eq!(
+            args,
+            [
+                "run",
+                "prepare-commit-msg",
+                "--no-auto-install",
+                "--",
+                ".git/COMMIT_EDITMSG",
+                "commit",
+                "abc123"
+            ]
+        );
+        // {1}, {2}, {3} are the arguments after the hook name and separator
+        let dash = args.iter().position(|a| a == "--").unwrap();
+        assert_eq!(&args[dash + 1..], git_args.as_slice());
+    }
+
+    #[test]
+    fn test_prepare_commit_msg_forwards_file_source_and_sha() {
+        let cwd = Some(Path::new("/work/repo"));
+        let forwarded = |git_args: &[&str]| {
+            let git_args = git_args.iter().map(|s| s.to_string()).collect();
+            let args = lefthook_args(
+                "prepare-commit-msg",
+                &[],
+                &absolutize_message_file("prepare-commit-msg", git_args, cwd),
+            );
+            let dash = args.iter().position(|a| a == "--").unwrap();
+            args[dash + 1..].to_vec()
+        };
+
+        // {1} message file, {2} commit source, {3} SHA; only {1} is rewritten
+        assert_eq!(
+            forwarded(&[".git/COMMIT_EDITMSG", "commit", "abc123"]),
+            ["/work/repo/.git/COMMIT_EDITMSG", "commit", "abc123"]
+        );
+        assert_eq!(
+            forwarded(&[".git/MERGE_MSG", "merge"]),
+            ["/work/repo/.git/MERGE_MSG", "merge"]
+        );
+        // plain `git commit`: no source, so {2} and {3} stay empty
+        assert_eq!(forwarded(&[".git/COMMIT_EDITMSG"]), ["/work/repo/.git/COMMIT_EDITMSG"]);
+    }
+
+    #[test]
+    fn test_lefthook_args_dash_prefixed_git_arg_stays_positional() {
+        let git_args = vec!["-weird-file".to_string()];
+        let args = lefthook_args("commit-msg", &[], &git_args);
+        assert_eq!(args, ["run", "commit-msg", "--no-auto-install", "--", "-weird-file"]);
+    }
+
+    #[test]
+    fn test_absolutize_message_file_commit_msg() {
+        let args = absolutize_message_file(
+            "commit-msg",
+            vec![".git/COMMIT_EDITMSG".to_string()],
+            Some(Path::new("/work/repo")),
+        );
+        assert_eq!(args, ["/work/repo/.git/COMMIT_EDITMSG"]);
+
+        // `{1}` in `run: commitlint --edit {1}` resolves to the first arg after `--`
+        let lefthook = lefthook_args("commit-msg", &[], &args);
+        let dash = lefthook.iter().position(|a| a == "--").unwrap();
+        assert_eq!(lefthook[dash + 1], "/work/repo/.git/COMMIT_EDITMSG");
+    }
+
+    #[test]
+    fn test_absolutize_message_file_keeps_other_args() {
+        let cwd = Some(Path::new("/work/repo"));
+        let args = absolutize_message_file(
+            "prepare-commit-msg",
+            vec![".git/COMMIT_EDITMSG".to_string(), "message".to_string()],
+            cwd,
+        );
+        assert_eq!(args, ["/work/repo/.git/COMMIT_EDITMSG", "message"]);
+
+        let abs = absolutize_message_file("commit-msg", vec!["/tmp/msg".to_string()], cwd);
+        assert_eq!(abs, ["/tmp/msg"]);
+
+        let push = absolutize_message_file("pre-push", vec!["origin".to_string()], cwd);
+        assert_eq!(push, ["origin"]);
+
+        assert!(absolutize_message_file("commit-msg", vec![], cwd).is_empty());
+    }
+
+    #[test]
+    fn test_lefthook_args_extra_flags_before_git_args() {
+        let extra = lefthook_extra_args(Some("--force --commands 'lint fmt'"));
+        let git_args = vec!["--force".to_string(), ".git/COMMIT_EDITMSG".to_string()];
+        assert_eq!(
+            lefthook_args("commit-msg", &extra, &git_args),
+            [
+                "run",
+                "commit-msg",
+                "--no-auto-install",
+                "--force",
+                "--commands",
+                "lint fmt",
+                "--",
+                "--force",
+                ".git/COMMIT_EDITMSG"
+            ]
+        );
+        assert_eq!(
+            lefthook_args("pre-commit", &extra, &[]),
+            [
+                "run",
+                "pre-commit",
+                "--no-auto-install",
+                "--force",
+                "--commands",
+                "lint fmt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lefthook_extra_args() {
+        assert!(lefthook_extra_args(None).is_empty());
+        assert!(lefthook_extra_args(Some("  ")).is_empty());
+        assert_eq!(lefthook_extra_args(Some("--verbose -- x")), ["--verbose", "x"]);
+        assert!(lefthook_extra_args(Some("--commands 'lint")).is_empty());
+    }
+
+    #[test]
+    fn test_shell_words() {
+        assert_eq!(
+            shell_words(r#"a "b c" 'd "e"' f\ g"#).unwrap(),
+            ["a", "b c", "d \"e\"", "f g"]
+        );
+        assert_eq!(shell_words(r#""say \"hi\"" '' x"#).unwrap(), ["say \"hi\"", "", "x"]);
+        assert_eq!(shell_words(r#"pre"fix"ed"#).unwrap(), ["prefixed"]);
+        assert!(shell_words("'open").is_err());
+        assert!(shell_words("\"open").is_err());
+        assert!(shell_words("trailing\\").is_err());
+    }
+
+    #[test]
+    fn test_lefthook_args_no_git_args() {
+        assert_eq!(
+            lefthook_args("pre-commit", &[], &[]),
+            ["run", "pre-commit", "--no-auto-install"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_git_path() {
+        let root = Path::new("/work/repo");
+        assert_eq!(
+            resolve_git_path(root, ".git/hooks"),
+            PathBuf::from("/work/repo/.git/hooks")
+        );
+        assert_eq!(
+            resolve_git_path(root, "/work/main/.git/hooks"),
+            PathBuf::from("/work/main/.git/hooks")
+        );
+    }
+
+    #[test]
+    fn test_git_hooks_dir_in_linked_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        let worktree = dir.path().join("wt");
+        let git = |args: &[&str], cwd: &Path| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "core.hooksPath="])
+                .args(args)
+                .current_dir(cwd)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?}");
+        };
+        fs::create_dir_all(&main).unwrap();
+        git(&["init", "-q"], &main);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"], &main);
+        git(&["worktree", "add", "-q", worktree.to_str().unwrap()], &main);
+
+        assert!(worktree.join(".git").is_file());
+        let hooks = git_hooks_dir(&worktree);
+        assert_eq!(
+            fs::canonicalize(hooks.parent().unwrap()).unwrap(),
+            fs::canonicalize(main.join(".git")).unwrap()
+        );
+        assert_eq!(hooks.file_name().unwrap(), "hooks");
+    }
 
     #[test]
     fn test_run_git_hook_executes_script() {
@@ -1,24 +1,213 @@
+use crate::config::{LHM_SETTINGS_KEY, env_flag};
 use crate::hooks::is_hook_name;
+use crate::trace;
+use crate::validate::type_name;
+use log::{debug, info, warn};
+use serde_json::json;
 use serde_yaml::Value;
 
+/// A global task that a repo task of the same name replaced during merge.
+#[derive(Debug, PartialEq)]
+pub struct Override {
+    pub name: String,
+    /// Section the global task lived in (`commands`, `scripts` or `jobs`).
+    pub global_section: &'static str,
+    /// Section the winning repo task lives in.
+    pub repo_section: &'static str,
+}
+
+/// How same-named tasks and settings from the global and repo configs are
+/// reconciled, set with `lhm.merge_strategy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MergeStrategy {
+    /// Repo values replace global ones.
+    #[default]
+    RepoWins,
+    /// Global values replace repo ones.
+    GlobalWins,
+    /// Like `RepoWins`, but same-named commands and scripts are merged field
+    /// by field instead of replaced.
+    Deep,
+}
+
+impl MergeStrategy {
+    /// Values accepted for `lhm.merge_strategy`.
+    pub const NAMES: &[&str] = &["repo-wins", "global-wins", "deep"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "repo-wins" => Ok(Self::RepoWins),
+            "global-wins" => Ok(Self::GlobalWins),
+            "deep" => Ok(Self::Deep),
+            other => Err(format!(
+                "unknown merge strategy `{other}` (expected one of: {})",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
 /// Merge two lefthook configs. Repo takes precedence over global.
 pub fn merge_configs(global: Value, repo: Value) -> Value {
+    merge_configs_with(global, repo, MergeStrategy::RepoWins)
+}
+
+/// Merge two lefthook configs using `strategy`. `GlobalWins` merges with the
+/// layers swapped, so trace and override logs name the global config "repo".
+/// The `lhm` settings are merged key by key with the repo's winning whatever
+/// the strategy, matching how `lhm_flag` and `merge_strategy` read them.
+pub fn merge_configs_with(global: Value, repo: Value, strategy: MergeStrategy) -> Value {
+    match strategy {
+        MergeStrategy::RepoWins => merge_layers(global, repo, false),
+        MergeStrategy::GlobalWins => {
+            let settings = merge_settings(
+                global.get(LHM_SETTINGS_KEY).cloned(),
+                repo.get(LHM_SETTINGS_KEY).cloned(),
+            );
+            let mut merged = merge_layers(repo, global, false);
+            if let (Some(settings), Value::Mapping(root)) = (settings, &mut merged) {
+                root.insert(LHM_SETTINGS_KEY.into(), settings);
+            }
+            merged
+        }
+        MergeStrategy::Deep => merge_layers(global, repo, true),
+    }
+}
+
+/// The `lhm` settings of two layers, `repo`'s replacing `global`'s per key.
+fn merge_settings(global: Option<Value>, repo: Option<Value>) -> Option<Value> {
+    match (global, repo) {
+        (Some(Value::Mapping(mut global)), Some(Value::Mapping(repo))) => {
+            global.extend(repo);
+            Some(Value::Mapping(global))
+        }
+        (global, repo) => repo.filter(|r| !r.is_null()).or(global),
+    }
+}
+
+fn merge_layers(global: Value, repo: Value, deep: bool) -> Value {
     match (global, repo) {
         (Value::Mapping(mut global), Value::Mapping(repo)) => {
+            for (key, _) in global.iter().filter(|(k, _)| !repo.contains_key(*k)) {
+                trace_key(key, "global");
+            }
             for (key, repo_val) in repo {
                 let key_str = key.as_str().unwrap_or("");
+                let in_global = global.contains_key(&key);
                 if is_hook_name(key_str) {
                     if let Some(global_val) = global.remove(&key) {
-                        global.insert(key, merge_hook(global_val, repo_val));
+                        let overrides = find_overrides(&global_val, &repo_val);
+                        trace_overrides(key_str, &overrides);
+                        report_overrides(key_str, &overrides);
+                        global.insert(key.clone(), merge_hook(global_val, repo_val, deep));
                     } else {
-                        global.insert(key, repo_val);
+                        global.insert(key.clone(), repo_val);
                     }
+                    trace_key(&key, if in_global { "merged" } else { "repo" });
+                } else if key_str == LHM_SETTINGS_KEY {
+                    let merged = merge_settings(global.remove(&key), Some(repo_val)).unwrap_or(Value::Null);
+                    global.insert(key.clone(), merged);
+                    trace_key(&key, if in_global { "merged" } else { "repo" });
+                } else if key_str == "output" {
+                    let merged = match global.remove(&key) {
+                        Some(global_val) => merge_output(global_val, repo_val),
+                        None => repo_val,
+                    };
+                    global.insert(key.clone(), merged);
+                    trace_key(&key, if in_global { "merged" } else { "repo" });
                 } else {
-                    global.insert(key, repo_val);
+                    if let Some(msg) = global.get(&key).and_then(|g| type_change(key_str, g, &repo_val)) {
+                        warn!("{msg}");
+                    }
+                    global.insert(key.clone(), repo_val);
+                    trace_key(&key, "repo");
                 }
             }
             Value::Mapping(global)
         }
+        // An empty or comment-only file parses to null: nothing to override
+        (global, Value::Null) => global,
+        (_, repo) => repo,
+    }
+}
+
+/// Like `merge_configs_with`, but in strict mode refuses to merge when a repo
+/// task replaces a global task of the same name whose `run` differs.
+pub fn merge_configs_checked(
+    global: Value,
+    repo: Value,
+    strict: bool,
+    strategy: MergeStrategy,
+) -> Result<Value, String> {
+    if strict {
+        let conflicts = find_conflicts(&global, &repo);
+        if !conflicts.is_empty() {
+            return Err(format!(
+                "strict mode: repo and global define different `run` for: {}",
+                conflicts.join(", ")
+            ));
+        }
+    }
+    Ok(merge_configs_with(global, repo, strategy))
+}
+
+/// List `<hook>: <task>` pairs defined in both configs with differing `run` values.
+fn find_conflicts(global: &Value, repo: &Value) -> Vec<String> {
+    let (Some(global), Some(repo)) = (global.as_mapping(), repo.as_mapping()) else {
+        return Vec::new();
+    };
+    let mut conflicts = Vec::new();
+    for (key, repo_hook) in repo {
+        let Some(hook) = key.as_str().filter(|k| is_hook_name(k)) else {
+            continue;
+        };
+        let (Some(global_hook), Some(repo_hook)) =
+            (global.get(key).and_then(Value::as_mapping), repo_hook.as_mapping())
+        else {
+            continue;
+        };
+        let global_runs = task_runs(global_hook);
+        for (name, run) in task_runs(repo_hook) {
+            if global_runs.iter().any(|(n, r)| *n == name && *r != run) {
+                conflicts.push(format!("{hook}: {name}"));
+            }
+        }
+    }
+    conflicts
+}
+
+/// Named commands and jobs in a hook, paired with their `run` value.
+fn task_runs(hook: &serde_yaml::Mapping) -> Vec<(String, Option<&Value>)> {
+    let mut runs = Vec::new();
+    if let Some(Value::Mapping(cmds)) = hook.get("commands") {
+        for (name, cmd) in cmds {
+            if let Some(name) = name.as_str() {
+                runs.push((name.to_string(), cmd.get("run")));
+            }
+        }
+    }
+    if let Some(Value::Sequence(jobs)) = hook.get("jobs") {
+        for job in jobs {
+            if let Some(name) = job.get("name").and_then(Value::as_str) {
+                runs.push((name.to_string(), job.get("run")));
+            }
+        }
+    }
+    runs
+}
+
+/// Merge `output` settings. Lists are unioned (global order first, then new
+/// repo entries); any other form (e.g. `output: false`) is repo-wins.
+fn merge_output(global: Value, repo: Value) -> Value {
+    match (global, repo) {
+        (Value::Sequence(mut global), Value::Sequence(repo)) => {
+            for item in repo {
+                if !global.contains(&item) {
+                    global.push(item);
+                }
+            }
+            Value::Sequence(global)
+        }
         (_, repo) => repo,
     }
 }
@@ -27,28 +216,49 @@ pub fn merge_configs(global: Value, repo: Value) -> Value {
 /// For jobs lists, merge named jobs by name and append unnamed ones.
 /// When formats differ (commands vs jobs), repo names suppress matching global names.
 /// For all other keys, repo wins.
-fn merge_hook(global: Value, repo: Value) -> Value {
+fn merge_hook(global: Value, repo: Value, deep: bool) -> Value {
     match (global, repo) {
-        (Value::Mapping(mut global), Value::Mapping(repo)) => {
+        (Value::Mapping(mut global), Value::Mapping(mut repo)) => {
+            normalize_commands(&mut global);
+            normalize_commands(&mut repo);
+
             // Collect repo task names across all formats for cross-format dedup
-            let repo_task_names = collect_task_names_from_mapping(&repo);
+            let repo_tasks = collect_tasks_from_mapping(&repo);
+            let command_priorities = task_priorities(&global, "commands");
+            let script_priorities = task_priorities(&global, "scripts");
 
-            // Remove global tasks that are overridden by repo (cross-format)
-            if !repo_task_names.is_empty() {
-                strip_names_from_commands(&mut global, &repo_task_names);
-                strip_names_from_scripts(&mut global, &repo_task_names);
-                strip_names_from_jobs(&mut global, &repo_task_names);
+            // Remove global tasks that are overridden by repo (cross-format).
+            // Same-named jobs are kept so `merge_jobs` can merge them field by
+            // field, as are same-section commands and scripts when merging deep.
+            let overriding = |target: &str| -> Vec<String> {
+                repo_tasks
+                    .iter()
+                    .filter(|(_, section)| *section != target || (target != "jobs" && !deep))
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            };
+            if !repo_tasks.is_empty() {
+                strip_names_from_commands(&mut global, &overriding("commands"));
+                strip_names_from_scripts(&mut global, &overriding("scripts"));
+                strip_names_from_jobs(&mut global, &overriding("jobs"));
             }
 
             for (key, repo_val) in repo {
                 let key_str = key.as_str().unwrap_or("");
                 match key_str {
                     "commands" | "scripts" => {
-                        if let Some(global_val) = global.remove(&key) {
-                            global.insert(key, merge_maps(global_val, repo_val));
+                        let priorities = if key_str == "commands" {
+                            &command_priorities
                         } else {
-                            global.insert(key, repo_val);
-                        }
+                            &script_priorities
+                        };
+                        let repo_val = inherit_priorities(repo_val, priorities);
+                        let merged = match global.remove(&key) {
+                            Some(global_val) if deep => deep_merge(global_val, repo_val),
+                            Some(global_val) => merge_maps(global_val, repo_val),
+                            None => repo_val,
+                        };
+                        global.insert(key, sort_by_priority(merged));
                     }
                     "jobs" => {
                         if let Some(global_val) = global.remove(&key) {
@@ -69,15 +279,162 @@ fn merge_hook(global: Value, repo: Value) -> Value {
     }
 }
 
-fn collect_task_names_from_mapping(mapping: &serde_yaml::Mapping) -> Vec<String> {
-    let mut names = Vec::new();
+/// `priority` of each named task in a hook's `commands` or `scripts` section.
+fn task_priorities(hook: &serde_yaml::Mapping, section: &str) -> Vec<(Value, Value)> {
+    hook.get(section)
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, task)| Some((name.clone(), task.get("priority")?.clone())))
+        .collect()
+}
+
+/// Give repo tasks that replace a global task of the same name in the same
+/// section the global task's `priority` when they don't set one, so
+/// overriding `run` doesn't change where the task runs.
+fn inherit_priorities(tasks: Value, global_priorities: &[(Value, Value)]) -> Value {
+    let Value::Mapping(mut tasks) = tasks else {
+        return tasks;
+    };
+    for (name, priority) in global_priorities {
+        if let Some(Value::Mapping(task)) = tasks.get_mut(name)
+            && !task.contains_key("priority")
+        {
+            task.insert("priority".into(), priority.clone());
+        }
+    }
+    Value::Mapping(tasks)
+}
+
+/// Order a merged commands/scripts mapping by `priority` (lowest first), so
+/// the merged config lists tasks from both layers in run order. Tasks without
+/// a positive priority keep their relative order after the prioritized ones.
+fn sort_by_priority(tasks: Value) -> Value {
+    let Value::Mapping(tasks) = tasks else {
+        return tasks;
+    };
+    let priority = |task: &Value| task.get("priority").and_then(Value::as_u64).filter(|p| *p > 0);
+    if !tasks.values().any(|t| priority(t).is_some()) {
+        return Value::Mapping(tasks);
+    }
+    let mut entries: Vec<(Value, Value)> = tasks.into_iter().collect();
+    entries.sort_by_key(|(_, task)| priority(task).unwrap_or(u64::MAX));
+    Value::Mapping(entries.into_iter().collect())
+}
+
+/// Rewrite a sequence-form `commands` list into the mapping form keyed by
+/// command name, so it can be merged by name. Entries are either mappings with
+/// a `name` field or single-key `{name: {...}}` mappings; if any entry is
+/// neither, the list is left untouched.
+fn normalize_commands(hook: &mut serde_yaml::Mapping) {
+    let Some(Value::Sequence(items)) = hook.get("commands") else {
+        return;
+    };
+    let mut commands = serde_yaml::Mapping::new();
+    for item in items {
+        let Some(entry) = item.as_mapping() else {
+            return;
+        };
+        if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            let mut cmd = entry.clone();
+            cmd.remove("name");
+            commands.insert(Value::String(name.to_string()), Value::Mapping(cmd));
+        } else if let [(key, val @ Value::Mapping(_))] = entry.iter().collect::<Vec<_>>()[..]
+            && key.is_string()
+        {
+            commands.insert(key.clone(), val.clone());
+        } else {
+            return;
+        }
+    }
+    hook.insert(Value::String("commands".to_string()), Value::Mapping(commands));
+}
+
+/// Trace which layer a top-level key in the merged config came from.
+fn trace_key(key: &Value, layer: &str) {
+    trace::emit(json!({"event": "key", "key": key.as_str().unwrap_or(""), "layer": layer}));
+}
+
+/// Trace global tasks dropped in favor of a same-named repo task. Matching
+/// named jobs are merged field by field rather than stripped.
+fn trace_overrides(hook: &str, overrides: &[Override]) {
+    for o in overrides {
+        let event = if o.global_section == "jobs" && o.repo_section == "jobs" {
+            "merge_job"
+        } else {
+            "strip"
+        };
+        trace::emit(json!({
+            "event": event,
+            "hook": hook,
+            "name": o.name,
+            "global_section": o.global_section,
+            "repo_section": o.repo_section,
+        }));
+    }
+}
+
+/// Warning for a top-level setting whose overriding value has a different YAML
+/// type than the one it replaces (e.g. `colors: true` vs `colors: "auto"`).
+/// Worded without layer names, since includes, nested configs and adapter
+/// output go through the same merge. Null on either side counts as unset.
+fn type_change(key: &str, global: &Value, repo: &Value) -> Option<String> {
+    let (global_type, repo_type) = (type_name(global), type_name(repo));
+    if global.is_null() || repo.is_null() || global_type == repo_type {
+        return None;
+    }
+    Some(format!(
+        "`{key}` changes from a {global_type} to a {repo_type} between merged configs; using the overriding {repo_type}"
+    ))
+}
+
+/// Log the global tasks a repo hook replaces. Emitted at info level when
+/// `LHM_WARN_OVERRIDES=1`, otherwise only visible with debug logging.
+fn report_overrides(hook: &str, overrides: &[Override]) {
+    if overrides.is_empty() {
+        return;
+    }
+    let list: Vec<String> = overrides
+        .iter()
+        .map(|o| format!("{} (global {} -> repo {})", o.name, o.global_section, o.repo_section))
+        .collect();
+    let msg = format!("{hook}: repo overrides global task(s): {}", list.join(", "));
+    if env_flag("LHM_WARN_OVERRIDES") {
+        info!("{msg}");
+    } else {
+        debug!("{msg}");
+    }
+}
+
+/// List global tasks that will be replaced by a same-named repo task in any format.
+pub fn find_overrides(global: &Value, repo: &Value) -> Vec<Override> {
+    let (Some(global), Some(repo)) = (global.as_mapping(), repo.as_mapping()) else {
+        return Vec::new();
+    };
+    let repo_tasks = collect_tasks_from_mapping(repo);
+    collect_tasks_from_mapping(global)
+        .into_iter()
+        .filter_map(|(name, global_section)| {
+            let (_, repo_section) = repo_tasks.iter().find(|(n, _)| *n == name)?;
+            Some(Override {
+                name,
+                global_section,
+                repo_section,
+            })
+        })
+        .collect()
+}
+
+/// Collect task names paired with the section (`commands`, `scripts`, `jobs`) they appear in.
+fn collect_tasks_from_mapping(mapping: &serde_yaml::Mapping) -> Vec<(String, &'static str)> {
+    let mut tasks = Vec::new();
 
     // Names from commands/scripts (map keys)
     for section in ["commands", "scripts"] {
         if let Some(Value::Mapping(m)) = mapping.get(Value::String(section.to_string())) {
             for key in m.keys() {
                 if let Some(s) = key.as_str() {
-                    names.push(s.to_string());
+                    tasks.push((s.to_string(), section));
                 }
             }
         }
@@ -87,12 +444,12 @@ fn collect_task_names_from_mapping(mapping: &serde_yaml::Mapping) -> Vec<String>
     if let Some(Value::Sequence(jobs)) = mapping.get(Value::String("jobs".to_string())) {
         for job in jobs {
             if let Some(name) = job.as_mapping().and_then(|m| m.get("name")).and_then(|v| v.as_str()) {
-                names.push(name.to_string());
+                tasks.push((name.to_string(), "jobs"));
             }
         }
     }
 
-    names
+    tasks
 }
 
 fn strip_names_from_commands(mapping: &mut serde_yaml::Mapping, names: &[String]) {
@@ -143,8 +500,27 @@ fn merge_maps(global: Value, repo: Value) -> Value {
     }
 }
 
-/// Merge two jobs lists. Named jobs (with `name` field) are merged by name
-/// with repo taking precedence. Unnamed jobs are appended (global first, then repo).
+/// Merge two YAML values recursively: mappings are merged key by key, and
+/// anything else is taken from repo.
+fn deep_merge(global: Value, repo: Value) -> Value {
+    match (global, repo) {
+        (Value::Mapping(mut global), Value::Mapping(repo)) => {
+            for (key, repo_val) in repo {
+                let merged = match global.get(&key) {
+                    Some(global_val) => deep_merge(global_val.clone(), repo_val),
+                    None => repo_val,
+                };
+                global.insert(key, merged);
+            }
+            Value::Mapping(global)
+        }
+        (_, repo) => repo,
+    }
+}
+
+/// Merge two jobs lists. Named jobs (with `name` field) are merged by name,
+/// field by field with repo winning per key. Unnamed jobs are appended
+/// (global first, then repo).
 fn merge_jobs(global: Value, repo: Value) -> Value {
     match (&global, &repo) {
         (Value::Sequence(global_jobs), Value::Sequence(repo_jobs)) => {
@@ -166,8 +542,26 @@ fn merge_jobs(global: Value, repo: Value) -> Value {
                 result.push(job.clone());
             }
 
-            // Add all repo jobs
-            result.extend(repo_jobs.iter().cloned());
+            // Add all repo jobs, layered over the global job of the same name
+            for job in repo_jobs {
+                let global_job = job_name(job).and_then(|name| global_jobs.iter().find(|g| job_name(g) == Some(name)));
+                match (global_job, job) {
+                    (Some(Value::Mapping(global_fields)), Value::Mapping(repo_fields)) => {
+                        let mut merged = global_fields.clone();
+                        for (key, val) in repo_fields {
+                            let val = match merged.get(key) {
+                                Some(global_group) if key.as_str() == Some("group") => {
+                                    merge_group(global_group.clone(), val.clone())
+                                }
+                                _ => val.clone(),
+                            };
+                            merged.insert(key.clone(), val);
+                        }
+                        result.push(Value::Mapping(merged));
+                    }
+                    _ => result.push(job.clone()),
+                }
+            }
 
             Value::Sequence(result)
         }
@@ -175,6 +569,24 @@ fn merge_jobs(global: Value, repo: Value) -> Value {
     }
 }
 
+/// Merge the `group` mappings of two jobs with the same name: the inner
+/// `jobs` lists follow the `merge_jobs` rules, other keys are repo-wins.
+fn merge_group(global: Value, repo: Value) -> Value {
+    match (global, repo) {
+        (Value::Mapping(mut global), Value::Mapping(repo)) => {
+            for (key, repo_val) in repo {
+                let val = match global.get(&key) {
+                    Some(global_jobs) if key.as_str() == Some("jobs") => merge_jobs(global_jobs.clone(), repo_val),
+                    _ => repo_val,
+                };
+                global.insert(key, val);
+            }
+            Value::Mapping(global)
+        }
+        (_, repo) => repo,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,6 +610,102 @@ mod tests {
         assert!(out.contains("min_version"));
     }
 
+    #[test]
+    fn test_merge_configs_merges_lhm_settings_per_key() {
+        let global = yaml("lhm:\n  jobs: 1\n  merge_adapters: false\n");
+        let repo = yaml("lhm:\n  merge_adapters: true\n");
+        for strategy in [MergeStrategy::RepoWins, MergeStrategy::GlobalWins, MergeStrategy::Deep] {
+            let merged = merge_configs_with(global.clone(), repo.clone(), strategy);
+            assert_eq!(merged["lhm"]["jobs"], Value::from(1), "{strategy:?}");
+            assert_eq!(merged["lhm"]["merge_adapters"], Value::Bool(true), "{strategy:?}");
+        }
+        let merged = merge_configs(yaml("lhm:\n  jobs: 1\n"), yaml("pre-commit: {}\n"));
+        assert_eq!(merged["lhm"]["jobs"], Value::from(1));
+    }
+
+    #[test]
+    fn test_merge_configs_null_repo_keeps_global() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: lint\n");
+        assert_eq!(merge_configs(global.clone(), Value::Null), global);
+        assert_eq!(merge_configs(Value::Null, global.clone()), global);
+    }
+
+    #[test]
+    fn test_merge_configs_type_change_keeps_repo_value() {
+        let global = yaml("colors: true\nno_tty: false\n");
+        let repo = yaml("colors: auto\nno_tty: true\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(merged["colors"], Value::String("auto".into()));
+        assert_eq!(merged["no_tty"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_type_change_warning() {
+        assert_eq!(
+            type_change("colors", &yaml("true"), &yaml("auto")).unwrap(),
+            "`colors` changes from a bool to a string between merged configs; using the overriding string"
+        );
+        assert!(type_change("no_tty", &yaml("false"), &yaml("true")).is_none());
+        assert!(type_change("skip_output", &yaml("~"), &yaml("[meta]")).is_none());
+    }
+
+    #[test]
+    fn test_merge_configs_priorities_from_both_layers() {
+        let global = yaml(
+            "pre-commit:\n  commands:\n    secrets:\n      run: gitleaks\n      priority: 1\n    fmt:\n      run: global-fmt\n      priority: 3\n    notify:\n      run: notify\n",
+        );
+        let repo = yaml(
+            "pre-commit:\n  commands:\n    lint:\n      run: clippy\n      priority: 2\n    fmt:\n      run: cargo fmt\n",
+        );
+        let merged = merge_configs(global, repo);
+        let commands = merged["pre-commit"]["commands"].as_mapping().unwrap();
+        let order: Vec<_> = commands.keys().filter_map(Value::as_str).collect();
+        assert_eq!(order, ["secrets", "lint", "fmt", "notify"]);
+        assert_eq!(commands["secrets"]["priority"], yaml("1"));
+        assert_eq!(commands["lint"]["priority"], yaml("2"));
+        // The repo's fmt replaces the global one but keeps its slot
+        assert_eq!(commands["fmt"]["run"], yaml("cargo fmt"));
+        assert_eq!(commands["fmt"]["priority"], yaml("3"));
+    }
+
+    #[test]
+    fn test_merge_configs_priority_not_inherited_across_sections() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: global-lint\n      priority: 1\n");
+        let repo = yaml("pre-commit:\n  scripts:\n    lint:\n      runner: bash\n");
+        let merged = merge_configs(global, repo);
+        assert!(merged["pre-commit"]["scripts"]["lint"].get("priority").is_none());
+    }
+
+    #[test]
+    fn test_merge_configs_repo_priority_wins() {
+        let global = yaml("pre-push:\n  scripts:\n    check.sh:\n      runner: bash\n      priority: 1\n");
+        let repo = yaml("pre-push:\n  scripts:\n    check.sh:\n      runner: sh\n      priority: 5\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(merged["pre-push"]["scripts"]["check.sh"]["priority"], yaml("5"));
+    }
+
+    #[test]
+    fn test_merge_configs_output_union() {
+        let global = yaml("output:\n  - success\n  - failure\n");
+        let repo = yaml("output:\n  - failure\n  - summary\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(merged["output"], yaml("[success, failure, summary]"));
+    }
+
+    #[test]
+    fn test_merge_configs_output_scalar_repo_wins() {
+        let global = yaml("output:\n  - success\n");
+        let repo = yaml("output: false\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(merged["output"], Value::Bool(false));
+    }
+
+    #[test]
+    fn test_merge_configs_output_repo_only() {
+        let merged = merge_configs(yaml("skip_lfs: true\n"), yaml("output:\n  - failure\n"));
+        assert_eq!(merged["output"], yaml("[failure]"));
+    }
+
     #[test]
     fn test_merge_configs_commands_dedup() {
         let global =
@@ -210,6 +718,130 @@ mod tests {
         assert!(out.contains("global-lint"), "global-only lint preserved: {out}");
     }
 
+    #[test]
+    fn test_merge_configs_commands_sequence_vs_mapping() {
+        let global =
+            yaml("pre-commit:\n  commands:\n    lint:\n      run: global-lint\n    fmt:\n      run: global-fmt\n");
+        let repo = yaml("pre-commit:\n  commands:\n    - name: lint\n      run: repo-lint\n");
+        let merged = merge_configs(global, repo);
+        let cmds = &merged["pre-commit"]["commands"];
+        assert_eq!(cmds["lint"], yaml("run: repo-lint"));
+        assert_eq!(cmds["fmt"], yaml("run: global-fmt"));
+    }
+
+    #[test]
+    fn test_merge_configs_commands_mapping_vs_sequence() {
+        let global = yaml(
+            "pre-commit:\n  commands:\n    - lint:\n        run: global-lint\n    - fmt:\n        run: global-fmt\n",
+        );
+        let repo = yaml("pre-commit:\n  commands:\n    lint:\n      run: repo-lint\n");
+        let merged = merge_configs(global, repo);
+        let cmds = &merged["pre-commit"]["commands"];
+        assert_eq!(cmds["lint"], yaml("run: repo-lint"));
+        assert_eq!(cmds["fmt"], yaml("run: global-fmt"));
+    }
+
+    #[test]
+    fn test_merge_configs_commands_unnamed_sequence_left_as_is() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: global-lint\n");
+        let repo = yaml("pre-commit:\n  commands:\n    - run: anonymous\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(merged["pre-commit"]["commands"], yaml("[{run: anonymous}]"));
+    }
+
+    fn strategy_inputs() -> (Value, Value) {
+        let global = yaml(
+            "colors: false\npre-commit:\n  commands:\n    lint:\n      run: global-lint\n      glob: '*.rs'\n      env:\n        A: '1'\n",
+        );
+        let repo = yaml(
+            "colors: true\npre-commit:\n  commands:\n    lint:\n      run: repo-lint\n      env:\n        B: '2'\n",
+        );
+        (global, repo)
+    }
+
+    #[test]
+    fn test_merge_strategy_repo_wins() {
+        let (global, repo) = strategy_inputs();
+        let merged = merge_configs_with(global, repo, MergeStrategy::RepoWins);
+        assert_eq!(merged["colors"], Value::Bool(true));
+        let lint = &merged["pre-commit"]["commands"]["lint"];
+        assert_eq!(lint, &yaml("run: repo-lint\nenv:\n  B: '2'\n"));
+    }
+
+    #[test]
+    fn test_merge_strategy_global_wins() {
+        let (global, repo) = strategy_inputs();
+        let merged = merge_configs_with(global, repo, MergeStrategy::GlobalWins);
+        assert_eq!(merged["colors"], Value::Bool(false));
+        let lint = &merged["pre-commit"]["commands"]["lint"];
+        assert_eq!(lint, &yaml("run: global-lint\nglob: '*.rs'\nenv:\n  A: '1'\n"));
+    }
+
+    #[test]
+    fn test_merge_strategy_deep() {
+        let (global, repo) = strategy_inputs();
+        let merged = merge_configs_with(global, repo, MergeStrategy::Deep);
+        assert_eq!(merged["colors"], Value::Bool(true));
+        let lint = &merged["pre-commit"]["commands"]["lint"];
+        assert_eq!(lint, &yaml("run: repo-lint\nglob: '*.rs'\nenv:\n  A: '1'\n  B: '2'\n"));
+    }
+
+    #[test]
+    fn test_merge_strategy_deep_still_strips_cross_format() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: global-lint\n      glob: '*.rs'\n");
+        let repo = yaml("pre-commit:\n  scripts:\n    lint:\n      runner: bash\n");
+        let merged = merge_configs_with(global, repo, MergeStrategy::Deep);
+        assert!(merged["pre-commit"].get("commands").is_none(), "{}", to_yaml(&merged));
+        assert_eq!(merged["pre-commit"]["scripts"]["lint"], yaml("runner: bash\n"));
+    }
+
+    #[test]
+    fn test_merge_strategy_parse() {
+        assert_eq!(MergeStrategy::parse("repo-wins"), Ok(MergeStrategy::RepoWins));
+        assert_eq!(MergeStrategy::parse("global-wins"), Ok(MergeStrategy::GlobalWins));
+        assert_eq!(MergeStrategy::parse("deep"), Ok(MergeStrategy::Deep));
+        assert!(MergeStrategy::parse("newest").unwrap_err().contains("newest"));
+    }
+
+    #[test]
+    fn test_merge_configs_checked_strict_conflict() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: global-lint\n    fmt:\n      run: fmt\n");
+        let repo =
+            yaml("pre-commit:\n  jobs:\n    - name: lint\n      run: repo-lint\n    - name: fmt\n      run: fmt\n");
+        let err = merge_configs_checked(global.clone(), repo.clone(), true, MergeStrategy::RepoWins).unwrap_err();
+        assert!(err.contains("pre-commit: lint"), "{err}");
+        assert!(!err.contains("fmt"), "same run is not a conflict: {err}");
+
+        let merged = merge_configs_checked(global, repo, false, MergeStrategy::RepoWins).unwrap();
+        assert!(to_yaml(&merged).contains("repo-lint"));
+    }
+
+    #[test]
+    fn test_merge_configs_checked_strict_no_conflict() {
+        let global = yaml("pre-commit:\n  commands:\n    lint:\n      run: lint\n");
+        let repo = yaml("pre-commit:\n  commands:\n    test:\n      run: test\n");
+        assert!(merge_configs_checked(global, repo, true, MergeStrategy::RepoWins).is_ok());
+    }
+
+    #[test]
+    fn test_merge_configs_trace_strip_event() {
+        let global = yaml("pre-push:\n  commands:\n    lint:\n      run: global-lint\nmin_version: '1.0'\n");
+        let repo = yaml("pre-push:\n  jobs:\n    - name: lint\n      run: repo-lint\nskip_lfs: true\n");
+        let events = trace::capture(|| {
+            merge_configs(global, repo);
+        });
+        assert!(events.contains(&json!({
+            "event": "strip",
+            "hook": "pre-push",
+            "name": "lint",
+            "global_section": "commands",
+            "repo_section": "jobs",
+        })));
+        assert!(events.contains(&json!({"event": "key", "key": "min_version", "layer": "global"})));
+        assert!(events.contains(&json!({"event": "key", "key": "skip_lfs", "layer": "repo"})));
+        assert!(events.contains(&json!({"event": "key", "key": "pre-push", "layer": "merged"})));
+    }
+
     #[test]
     fn test_merge_configs_cross_format_commands_vs_jobs() {
         let global =
@@ -227,6 +859,26 @@ mod tests {
         assert!(out.contains("repo-lint"), "repo lint present: {out}");
     }
 
+    #[test]
+    fn test_merge_configs_cross_format_jobs_vs_commands() {
+        let global = yaml(
+            "pre-push:\n  jobs:\n    - name: test\n      run: global-test\n    - name: fmt\n      run: global-fmt\n    - run: global-unnamed\n",
+        );
+        let repo = yaml("pre-push:\n  commands:\n    test:\n      run: repo-test\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(
+            merged,
+            yaml(
+                "pre-push:\n  jobs:\n    - name: fmt\n      run: global-fmt\n    - run: global-unnamed\n  commands:\n    test:\n      run: repo-test\n",
+            )
+        );
+
+        // Overriding every global job leaves no empty `jobs` list behind
+        let global = yaml("pre-push:\n  jobs:\n    - name: test\n      run: global-test\n");
+        let merged = merge_configs(global, yaml("pre-push:\n  scripts:\n    test:\n      runner: bash\n"));
+        assert_eq!(merged, yaml("pre-push:\n  scripts:\n    test:\n      runner: bash\n"));
+    }
+
     #[test]
     fn test_merge_configs_global_only_hook_preserved() {
         let global = yaml("prepare-commit-msg:\n  commands:\n    aittributor:\n      run: aittributor\n");
@@ -238,6 +890,28 @@ mod tests {
         assert!(out.contains("pre-commit"), "repo hook kept: {out}");
     }
 
+    #[test]
+    fn test_find_overrides_cross_format() {
+        let global = yaml("commands:\n  lint:\n    run: global-lint\n  fmt:\n    run: global-fmt\n");
+        let repo = yaml("scripts:\n  lint:\n    runner: bash\n");
+        let overrides = find_overrides(&global, &repo);
+        assert_eq!(
+            overrides,
+            vec![Override {
+                name: "lint".to_string(),
+                global_section: "commands",
+                repo_section: "scripts",
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_overrides_none_without_collision() {
+        let global = yaml("commands:\n  lint:\n    run: global-lint\n");
+        let repo = yaml("jobs:\n  - name: test\n    run: repo-test\n");
+        assert!(find_overrides(&global, &repo).is_empty());
+    }
+
     #[test]
     fn test_merge_jobs_named_dedup() {
         let global = yaml("- name: test\n  run: global-test\n- name: unique\n  run: global-unique\n");
@@ -249,6 +923,57 @@ mod tests {
         assert!(out.contains("global-unique"), "global-only job kept: {out}");
     }
 
+    #[test]
+    fn test_merge_jobs_named_fields_merged() {
+        let global = yaml("- name: test\n  run: global-test\n  glob: '*.rs'\n  tags: [slow]\n");
+        let repo = yaml("- name: test\n  run: repo-test\n  tags: [fast]\n");
+        let merged = merge_jobs(global, repo);
+        assert_eq!(
+            merged,
+            yaml("- name: test\n  run: repo-test\n  glob: '*.rs'\n  tags: [fast]\n")
+        );
+    }
+
+    #[test]
+    fn test_merge_configs_named_job_override_keeps_global_fields() {
+        let global = yaml("pre-push:\n  jobs:\n    - name: test\n      run: global-test\n      glob: '*.rs'\n");
+        let repo = yaml("pre-push:\n  jobs:\n    - name: test\n      run: repo-test\n");
+        let merged = merge_configs(global, repo);
+        assert_eq!(
+            merged["pre-push"]["jobs"],
+            yaml("- name: test\n  run: repo-test\n  glob: '*.rs'\n")
+        );
+    }
+
+    #[test]
+    fn test_merge_jobs_group_inner_job_override() {
+        let global = yaml(
+            "- name: checks\n  group:\n    parallel: true\n    jobs:\n      - name: lint\n        run: global-lint\n        glob: '*.rs'\n      - name: fmt\n        run: global-fmt\n",
+        );
+        let repo = yaml(
+            "- name: checks\n  group:\n    jobs:\n      - name: lint\n        run: repo-lint\n      - name: audit\n        run: repo-audit\n",
+        );
+        let merged = merge_jobs(global, repo);
+        assert_eq!(
+            merged,
+            yaml(
+                "- name: checks\n  group:\n    parallel: true\n    jobs:\n      - name: fmt\n        run: global-fmt\n      - name: lint\n        run: repo-lint\n        glob: '*.rs'\n      - name: audit\n        run: repo-audit\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge_jobs_nested_groups() {
+        let global = yaml(
+            "- name: outer\n  group:\n    jobs:\n      - name: inner\n        group:\n          jobs:\n            - name: test\n              run: global-test\n",
+        );
+        let repo = yaml(
+            "- name: outer\n  group:\n    jobs:\n      - name: inner\n        group:\n          jobs:\n            - name: test\n              run: repo-test\n",
+        );
+        let merged = merge_jobs(global, repo.clone());
+        assert_eq!(merged, repo);
+    }
+
     #[test]
     fn test_merge_jobs_unnamed_appended() {
         let global = yaml("- run: global-unnamed\n");
@@ -0,0 +1,227 @@
+use std::env;
+use std::io;
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long lefthook gets to exit after SIGTERM on timeout before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Read the lefthook timeout from `LHM_TIMEOUT_SECS`.
+/// Unset, empty, zero or unparsable values mean no timeout.
+pub fn timeout_from_env() -> Option<Duration> {
+    parse_timeout(env::var("LHM_TIMEOUT_SECS").ok().as_deref())
+}
+
+fn parse_timeout(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Run `cmd` to completion, stopping it (and its process group on Unix) if it
+/// outlives `timeout`: SIGTERM first, then SIGKILL after a grace period.
+/// Returns `Ok(None)` when the timeout fired.
+pub fn run_with_timeout(cmd: &mut Command, timeout: Option<Duration>) -> io::Result<Option<ExitStatus>> {
+    run_with_grace(cmd, timeout, KILL_GRACE)
+}
+
+fn run_with_grace(cmd: &mut Command, timeout: Option<Duration>, grace: Duration) -> io::Result<Option<ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return cmd.status().map(Some);
+    };
+
+    // Own process group so the whole tree can be stopped on timeout. On a
+    // terminal that group is made the foreground one, so Ctrl-C still reaches
+    // lefthook and interactive commands can read and write the terminal.
+    #[cfg(unix)]
+    let terminal = terminal::foreground();
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+        if let Some(fd) = terminal {
+            // SAFETY: only async-signal-safe calls between fork and exec
+            unsafe {
+                cmd.pre_exec(move || {
+                    terminal::hand_over(fd, libc::getpid());
+                    Ok(())
+                });
+            }
+        }
+    }
+
+    let mut child = cmd.spawn()?;
+    #[cfg(unix)]
+    let _restore = terminal.map(|fd| terminal::Foreground::take(fd, child.id() as libc::pid_t));
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            stop(&mut child, grace);
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// SIGTERM `child`'s process group, give it `grace` to exit, then SIGKILL
+/// whatever is left of the group.
+fn stop(child: &mut Child, grace: Duration) {
+    #[cfg(unix)]
+    {
+        let group = -(child.id() as libc::pid_t);
+        // SAFETY: signalling the process group we created for the child
+        unsafe {
+            libc::kill(group, libc::SIGTERM);
+        }
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline && matches!(child.try_wait(), Ok(None)) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        // SAFETY: as above; also reaps stragglers after lefthook itself exited
+        unsafe {
+            libc::kill(group, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = grace;
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(unix)]
+mod terminal {
+    use std::os::fd::RawFd;
+
+    /// Stdin, if it is a terminal on which lhm's process group is in the
+    /// foreground. Otherwise lefthook has no terminal to hand over.
+    pub fn foreground() -> Option<RawFd> {
+        let fd = libc::STDIN_FILENO;
+        // SAFETY: plain queries on a file descriptor
+        let foreground = unsafe { libc::isatty(fd) == 1 && libc::tcgetpgrp(fd) == libc::getpgrp() };
+        foreground.then_some(fd)
+    }
+
+    /// Make `group` the foreground process group of terminal `fd`. Callers
+    /// outside the foreground group get SIGTTOU for this, so it is ignored
+    /// meanwhile. Async-signal-safe, for use between fork and exec.
+    pub fn hand_over(fd: RawFd, group: libc::pid_t) {
+        // SAFETY: sigaction-based signal() and tcsetpgrp are async-signal-safe
+        unsafe {
+            let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+            libc::tcsetpgrp(fd, group);
+            libc::signal(libc::SIGTTOU, previous);
+        }
+    }
+
+    /// Hands the terminal to a child's group, and back to lhm's when dropped.
+    pub struct Foreground {
+        fd: RawFd,
+    }
+
+    impl Foreground {
+        /// Also done in the child before exec; doing it here too means neither
+        /// side can race the other.
+        pub fn take(fd: RawFd, group: libc::pid_t) -> Self {
+            hand_over(fd, group);
+            Self { fd }
+        }
+    }
+
+    impl Drop for Foreground {
+        fn drop(&mut self) {
+            // SAFETY: getpgrp can't fail
+            hand_over(self.fd, unsafe { libc::getpgrp() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout(None), None);
+        assert_eq!(parse_timeout(Some("")), None);
+        assert_eq!(parse_timeout(Some("0")), None);
+        assert_eq!(parse_timeout(Some("abc")), None);
+        assert_eq!(parse_timeout(Some(" 30 ")), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_run_with_timeout_completes() {
+        let status = run_with_timeout(&mut Command::new("true"), Some(Duration::from_secs(5)))
+            .unwrap()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_run_with_timeout_no_timeout() {
+        let status = run_with_timeout(&mut Command::new("false"), None).unwrap().unwrap();
+        assert!(!status.success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_timeout_kills_sleeping_lefthook() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fake = dir.path().join("lefthook");
+        fs::write(&fake, "#!/bin/sh\nsleep 30 &\nwait\n").unwrap();
+        fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let start = Instant::now();
+        let result = run_with_timeout(&mut Command::new(&fake), Some(Duration::from_millis(200))).unwrap();
+        assert!(result.is_none(), "timed out");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_timeout_sends_sigterm_before_kill() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("terminated");
+        let fake = dir.path().join("lefthook");
+        let script = format!(
+            "#!/bin/sh\ntrap 'echo cleaned up > {}; exit 143' TERM\nsleep 30 &\nwait\n",
+            marker.display()
+        );
+        fs::write(&fake, script).unwrap();
+        fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = run_with_grace(
+            &mut Command::new(&fake),
+            Some(Duration::from_millis(200)),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(result.is_none(), "timed out");
+        assert_eq!(fs::read_to_string(&marker).unwrap(), "cleaned up\n");
+
+        // SIGTERM ignored: killed once the grace period is over
+        fs::write(&fake, "#!/bin/sh\ntrap '' TERM\nsleep 30 &\nwait\n").unwrap();
+        let start = Instant::now();
+        let result = run_with_grace(
+            &mut Command::new(&fake),
+            Some(Duration::from_millis(200)),
+            Duration::from_millis(300),
+        )
+        .unwrap();
+        assert!(result.is_none(), "timed out");
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+    }
+}
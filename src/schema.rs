@@ -0,0 +1,129 @@
+use crate::config::{LHM_SETTINGS, LHM_SETTINGS_KEY};
+use crate::hooks::{GIT_HOOKS, LHM_RETRY_KEY, LHM_SOURCE_KEY};
+use crate::merge::MergeStrategy;
+use crate::validate::KNOWN_SETTINGS;
+use serde_json::{Map, Value, json};
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema for the lefthook config lhm reads, built from the hook and
+/// setting names lhm knows about so it can't drift from the code.
+pub fn config_schema() -> Value {
+    let mut properties = Map::new();
+    for setting in KNOWN_SETTINGS {
+        let schema = if *setting == LHM_SETTINGS_KEY {
+            json!({"$ref": "#/$defs/lhm"})
+        } else {
+            json!({})
+        };
+        properties.insert(setting.to_string(), schema);
+    }
+    for hook in GIT_HOOKS {
+        properties.insert(hook.to_string(), json!({"$ref": "#/$defs/hook"}));
+    }
+
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "lefthook config (as merged by lhm)",
+        "type": "object",
+        "properties": properties,
+        // Any other key is a custom hook (e.g. `manual`), run with `lefthook run <name>`.
+        "additionalProperties": {"$ref": "#/$defs/hook"},
+        "$defs": {
+            "lhm": lhm_settings_schema(),
+            "hook": {
+                "type": "object",
+                "properties": {
+                    "commands": {
+                        "oneOf": [
+                            {"type": "object", "additionalProperties": {"$ref": "#/$defs/task"}},
+                            {"type": "array", "items": {"type": "object"}},
+                        ],
+                    },
+                    "scripts": {"type": "object", "additionalProperties": {"$ref": "#/$defs/task"}},
+                    "jobs": {"type": "array", "items": {"$ref": "#/$defs/job"}},
+                },
+            },
+            "task": {
+                "type": "object",
+                "properties": {
+                    (LHM_RETRY_KEY): {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Retry a failing `run` up to this many times; stripped before lefthook runs.",
+                    },
+                    (LHM_SOURCE_KEY): {
+                        "type": "string",
+                        "description": "Adapter that generated this task; stripped before lefthook runs.",
+                    },
+                },
+            },
+            "job": {
+                "allOf": [{"$ref": "#/$defs/task"}],
+                "properties": {
+                    "name": {"type": "string"},
+                    "group": {
+                        "type": "object",
+                        "properties": {"jobs": {"type": "array", "items": {"$ref": "#/$defs/job"}}},
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Schema of the `lhm` settings mapping, one property per `LHM_SETTINGS` key.
+fn lhm_settings_schema() -> Value {
+    let hook_names: Vec<&str> = GIT_HOOKS.to_vec();
+    let mut properties = Map::new();
+    for setting in LHM_SETTINGS {
+        let schema = match *setting {
+            "disable_adapters" | "include" => json!({"type": "array", "items": {"type": "string"}}),
+            "jobs" => json!({"type": "integer", "minimum": 1}),
+            "merge_adapters" | "nested_configs" => json!({"type": "boolean"}),
+            "merge_strategy" => json!({"enum": MergeStrategy::NAMES}),
+            "output" => json!({"type": "object", "propertyNames": {"enum": hook_names}}),
+            _ => json!({}),
+        };
+        properties.insert(setting.to_string(), schema);
+    }
+    json!({
+        "type": "object",
+        "description": "lhm settings; removed before the config reaches lefthook.",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_schema_is_valid_json_listing_hooks() {
+        let text = serde_json::to_string_pretty(&config_schema()).unwrap();
+        let schema: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(schema["$schema"], SCHEMA_DIALECT);
+        for hook in GIT_HOOKS {
+            assert_eq!(schema["properties"][hook]["$ref"], "#/$defs/hook", "{hook}");
+        }
+        for setting in LHM_SETTINGS {
+            assert!(schema["$defs"]["lhm"]["properties"].get(setting).is_some(), "{setting}");
+        }
+        assert!(schema["$defs"]["task"]["properties"].get(LHM_RETRY_KEY).is_some());
+        assert!(schema["$defs"]["task"]["properties"].get(LHM_SOURCE_KEY).is_some());
+        assert_eq!(schema["additionalProperties"]["$ref"], "#/$defs/hook");
+    }
+
+    #[test]
+    fn test_merge_strategy_enum_matches_parser() {
+        let schema = config_schema();
+        let names = schema["$defs"]["lhm"]["properties"]["merge_strategy"]["enum"]
+            .as_array()
+            .unwrap()
+            .clone();
+        for name in names {
+            assert!(MergeStrategy::parse(name.as_str().unwrap()).is_ok(), "{name}");
+        }
+    }
+}
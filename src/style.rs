@@ -0,0 +1,40 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether status output should be colored: stdout is a terminal and
+/// `NO_COLOR` is unset or empty (see <https://no-color.org>).
+pub fn color_enabled() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && io::stdout().is_terminal()
+}
+
+/// Marker for a passing (`✓`) or failing (`✗`) check.
+pub fn marker(ok: bool, color: bool) -> String {
+    let (symbol, code) = if ok { ("✓", GREEN) } else { ("✗", RED) };
+    if color {
+        format!("{code}{symbol}{RESET}")
+    } else {
+        symbol.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_plain() {
+        assert_eq!(marker(true, false), "✓");
+        assert_eq!(marker(false, false), "✗");
+    }
+
+    #[test]
+    fn test_marker_colored() {
+        assert_eq!(marker(true, true), "\x1b[32m✓\x1b[0m");
+        assert_eq!(marker(false, true), "\x1b[31m✗\x1b[0m");
+    }
+}
@@ -0,0 +1,46 @@
+use serde_json::Value;
+use std::cell::RefCell;
+
+use crate::config::env_flag;
+
+thread_local! {
+    /// Events captured by `capture` instead of being written to stderr.
+    static CAPTURED: RefCell<Option<Vec<Value>>> = const { RefCell::new(None) };
+}
+
+/// Emit one merge-pipeline event as a JSON line on stderr when `LHM_TRACE=1`.
+pub fn emit(event: Value) {
+    let captured = CAPTURED.with(|c| match c.borrow_mut().as_mut() {
+        Some(events) => {
+            events.push(event.clone());
+            true
+        }
+        None => false,
+    });
+    if !captured && env_flag("LHM_TRACE") {
+        eprintln!("{event}");
+    }
+}
+
+/// Run `f` and return the events it emitted, regardless of `LHM_TRACE`.
+#[cfg(test)]
+pub fn capture(f: impl FnOnce()) -> Vec<Value> {
+    CAPTURED.with(|c| *c.borrow_mut() = Some(Vec::new()));
+    f();
+    CAPTURED.with(|c| c.borrow_mut().take().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_capture_collects_events() {
+        let events = capture(|| {
+            emit(json!({"event": "a"}));
+            emit(json!({"event": "b"}));
+        });
+        assert_eq!(events, [json!({"event": "a"}), json!({"event": "b"})]);
+    }
+}
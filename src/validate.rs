@@ -0,0 +1,352 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::hooks::{GIT_HOOKS, is_hook_name};
+
+/// Top-level lefthook settings that aren't hooks.
+pub const KNOWN_SETTINGS: &[&str] = &[
+    "assert_lefthook_installed",
+    "colors",
+    "extends",
+    "glob_matcher",
+    "lefthook",
+    "lhm",
+    "min_version",
+    "no_tty",
+    "output",
+    "rc",
+    "remotes",
+    "skip_lfs",
+    "skip_output",
+    "source_dir",
+    "source_dir_local",
+    "templates",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Severity {
+    /// The config can't be used as-is.
+    Error,
+    /// Legal but likely a mistake.
+    Warning,
+}
+
+#[derive(Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+/// Check a lefthook config for structural problems.
+pub fn validate_config(config: &Value) -> Vec<Issue> {
+    let Value::Mapping(root) = config else {
+        return vec![Issue::error(format!(
+            "config must be a mapping, found {}",
+            type_name(config)
+        ))];
+    };
+
+    let mut issues = Vec::new();
+    for (key, val) in root {
+        let Some(name) = key.as_str() else {
+            issues.push(Issue::error(format!("non-string top-level key: {key:?}")));
+            continue;
+        };
+        if is_hook_name(name) {
+            validate_hook(name, val, &mut issues);
+        } else if KNOWN_SETTINGS.contains(&name) {
+            continue;
+        } else if let Some(hook) = similar_hook_name(name) {
+            issues.push(Issue::warning(format!(
+                "unknown top-level key `{name}` (did you mean `{hook}`?)"
+            )));
+        } else if is_custom_hook(val) {
+            // Custom hooks like `manual` are run explicitly with `lefthook run <name>`.
+            validate_hook(name, val, &mut issues);
+        } else {
+            issues.push(Issue::warning(format!(
+                "unknown top-level key `{name}` (not a git hook or lefthook setting)"
+            )));
+        }
+    }
+    issues
+}
+
+fn validate_hook(hook: &str, val: &Value, issues: &mut Vec<Issue>) {
+    let Value::Mapping(hook_map) = val else {
+        issues.push(Issue::error(format!(
+            "{hook}: must be a mapping, found {}",
+            type_name(val)
+        )));
+        return;
+    };
+
+    match hook_map.get("commands") {
+        Some(Value::Sequence(items)) => validate_command_list(hook, items, issues),
+        Some(commands) => validate_named_section(hook, "commands", "run", commands, issues),
+        None => {}
+    }
+    if let Some(scripts) = hook_map.get("scripts") {
+        validate_named_section(hook, "scripts", "runner", scripts, issues);
+    }
+    if let Some(jobs) = hook_map.get("jobs") {
+        validate_jobs(hook, jobs, issues);
+    }
+    if !["commands", "scripts", "jobs"]
+        .iter()
+        .any(|k| hook_map.contains_key(*k))
+    {
+        issues.push(Issue::warning(format!("{hook}: defines no commands, scripts or jobs")));
+    }
+}
+
+/// Validate a `commands`/`scripts` mapping where every entry needs `required`.
+fn validate_named_section(hook: &str, section: &str, required: &str, val: &Value, issues: &mut Vec<Issue>) {
+    let Value::Mapping(entries) = val else {
+        issues.push(Issue::error(format!(
+            "{hook}.{section}: must be a mapping, found {}",
+            type_name(val)
+        )));
+        return;
+    };
+    for (name, entry) in entries {
+        let name = name.as_str().unwrap_or("?");
+        match entry {
+            Value::Mapping(m) if !m.contains_key(required) => {
+                issues.push(Issue::error(format!("{hook}.{section}.{name}: missing `{required}`")));
+            }
+            Value::Mapping(_) => {}
+            other => issues.push(Issue::error(format!(
+                "{hook}.{section}.{name}: must be a mapping, found {}",
+                type_name(other)
+            ))),
+        }
+    }
+}
+
+/// Validate sequence-form `commands`: each entry is a mapping with a `name`
+/// or a single `<name>: {...}` key, as `merge` accepts, and needs `run`.
+fn validate_command_list(hook: &str, items: &[Value], issues: &mut Vec<Issue>) {
+    for (i, item) in items.iter().enumerate() {
+        let Value::Mapping(entry) = item else {
+            issues.push(Issue::error(format!(
+                "{hook}.commands[{i}]: must be a mapping, found {}",
+                type_name(item)
+            )));
+            continue;
+        };
+        let (name, command) = if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            (name, entry)
+        } else if let [(Value::String(name), Value::Mapping(command))] = entry.iter().collect::<Vec<_>>()[..] {
+            (name.as_str(), command)
+        } else {
+            issues.push(Issue::error(format!(
+                "{hook}.commands[{i}]: needs a `name` or a single `<name>: {{...}}` key"
+            )));
+            continue;
+        };
+        if !command.contains_key("run") {
+            issues.push(Issue::error(format!("{hook}.commands.{name}: missing `run`")));
+        }
+    }
+}
+
+fn validate_jobs(hook: &str, val: &Value, issues: &mut Vec<Issue>) {
+    let Value::Sequence(jobs) = val else {
+        issues.push(Issue::error(format!(
+            "{hook}.jobs: must be a list, found {}",
+            type_name(val)
+        )));
+        return;
+    };
+    for (i, job) in jobs.iter().enumerate() {
+        let Value::Mapping(job_map) = job else {
+            issues.push(Issue::error(format!(
+                "{hook}.jobs[{i}]: must be a mapping, found {}",
+                type_name(job)
+            )));
+            continue;
+        };
+        if !has_any(job_map, &["run", "script", "group"]) {
+            issues.push(Issue::error(format!(
+                "{hook}.jobs[{i}]: needs one of `run`, `script` or `group`"
+            )));
+        }
+    }
+}
+
+/// A mapping with commands, scripts or jobs under a non-git-hook name.
+fn is_custom_hook(val: &Value) -> bool {
+    matches!(val, Value::Mapping(m) if has_any(m, &["commands", "scripts", "jobs"]))
+}
+
+/// The git hook `name` is most likely a typo of, if any.
+fn similar_hook_name(name: &str) -> Option<&'static str> {
+    GIT_HOOKS.iter().copied().find(|hook| edit_distance(name, hook) <= 2)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+fn has_any(mapping: &Mapping, keys: &[&str]) -> bool {
+    keys.iter().any(|k| mapping.contains_key(*k))
+}
+
+pub fn type_name(val: &Value) -> &'static str {
+    match val {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "sequence",
+        Value::Mapping(_) => "mapping",
+        Value::Tagged(_) => "tagged value",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    fn errors(issues: &[Issue]) -> Vec<&str> {
+        issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| i.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_valid_config() {
+        let config = yaml(
+            r#"
+output:
+  - success
+pre-commit:
+  commands:
+    fmt:
+      run: just fmt
+pre-push:
+  jobs:
+    - name: test
+      run: just test
+    - group:
+        jobs:
+          - run: just lint
+"#,
+        );
+        let issues = validate_config(&config);
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn test_validate_malformed_config() {
+        let config = yaml(
+            r#"
+pre-commit:
+  commands:
+    fmt:
+      stage_fixed: true
+pre-push:
+  jobs:
+    - name: test
+    - just lint
+"#,
+        );
+        let issues = validate_config(&config);
+        let errs = errors(&issues);
+        assert_eq!(errs.len(), 3, "{issues:?}");
+        assert!(errs.contains(&"pre-commit.commands.fmt: missing `run`"));
+        assert!(errs.contains(&"pre-push.jobs[0]: needs one of `run`, `script` or `group`"));
+        assert!(errs.contains(&"pre-push.jobs[1]: must be a mapping, found string"));
+    }
+
+    #[test]
+    fn test_validate_unknown_key_is_warning() {
+        let config = yaml("pre-comit:\n  commands:\n    fmt:\n      run: just fmt\n");
+        let issues = validate_config(&config);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(issues[0].message.contains("did you mean `pre-commit`"));
+    }
+
+    #[test]
+    fn test_validate_unknown_setting_is_warning() {
+        let issues = validate_config(&yaml("colours: true\n"));
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("not a git hook or lefthook setting"));
+    }
+
+    #[test]
+    fn test_validate_command_list() {
+        let config =
+            yaml("pre-commit:\n  commands:\n    - name: lint\n      run: lint\n    - fmt:\n        run: fmt\n");
+        assert!(validate_config(&config).is_empty());
+
+        let config = yaml(
+            "pre-commit:\n  commands:\n    - name: lint\n      glob: '*.rs'\n    - fmt: {}\n      test: {}\n    - lint\n",
+        );
+        assert_eq!(
+            errors(&validate_config(&config)),
+            [
+                "pre-commit.commands.lint: missing `run`",
+                "pre-commit.commands[1]: needs a `name` or a single `<name>: {...}` key",
+                "pre-commit.commands[2]: must be a mapping, found string",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_custom_hook() {
+        let config = yaml("manual:\n  commands:\n    check-yaml:\n      run: check-yaml\n");
+        assert!(validate_config(&config).is_empty());
+
+        let config = yaml("manual:\n  commands:\n    check-yaml:\n      glob: '*.yaml'\n");
+        assert_eq!(
+            errors(&validate_config(&config)),
+            ["manual.commands.check-yaml: missing `run`"]
+        );
+    }
+
+    #[test]
+    fn test_validate_non_mapping_root() {
+        let issues = validate_config(&yaml("- pre-commit\n"));
+        assert_eq!(errors(&issues), ["config must be a mapping, found sequence"]);
+    }
+
+    #[test]
+    fn test_validate_jobs_must_be_list() {
+        let issues = validate_config(&yaml("pre-push:\n  jobs:\n    test:\n      run: x\n"));
+        assert_eq!(errors(&issues), ["pre-push.jobs: must be a list, found mapping"]);
+    }
+}